@@ -2,32 +2,262 @@
 pragma solidity ^0.8.22;
 
 import { Ownable } from "@openzeppelin/contracts/access/Ownable.sol";
+import { IERC20 } from "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+import { SafeERC20 } from "@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol";
 import { OApp, MessagingFee, Origin } from "@layerzerolabs/oapp-evm/contracts/oapp/OApp.sol";
 import { MessagingReceipt } from "@layerzerolabs/oapp-evm/contracts/oapp/OAppSender.sol";
 import { OAppOptionsType3 } from "@layerzerolabs/oapp-evm/contracts/oapp/libs/OAppOptionsType3.sol";
 import { BetlifyMsgCodec } from "./libs/BetlifyMsgCodec.sol";
+import { IStakeOFT, SendParam } from "./interfaces/IStakeOFT.sol";
 
 contract BetlifyEvmAdapter is Ownable, OApp, OAppOptionsType3 {
+    using SafeERC20 for IERC20;
+
+    // Only the Solana program issues these, and a batch can't be an item of a batch
+    error NotAnAction(BetlifyMsgCodec.MsgType msgType);
+    error BatchArgsMismatch(uint256 msgTypes, uint256 args);
+    // Solana only sends payouts, acks and failure notices back
+    error NotAResponse(BetlifyMsgCodec.MsgType msgType);
+    error NothingToWithdraw();
+    error StakeOftNotSet();
+
+    event ActionSent(bytes32 indexed guid, uint64 requestId, address indexed user);
+    event PayoutIssued(uint64 requestId, uint64 indexed poolId, address indexed recipient, uint64 amount);
+    event PayoutWithdrawn(address indexed recipient, uint256 amount);
+    event ActionAcked(bytes32 indexed guid, uint64 requestId, uint8 status, uint64 poolId, bytes32 bet);
+    event ActionFailed(bytes32 indexed guid, uint64 requestId, uint32 errorCode);
+
+    // What Solana reported back about a sent action. poolId is the global id for a CreatePool.
+    struct ActionResult {
+        bool acked;
+        uint8 status;
+        uint64 poolId;
+        bytes32 bet;
+        uint32 errorCode; // Set once Solana stored the action for retry
+    }
+
+    // Echoed back by Solana in acks, payouts and failure notices
+    uint64 public nextRequestId;
+    // Token PlaceBet stakes are escrowed in until Solana pays them out; bets are unfunded while unset.
+    // Also the token of stakeOft when stakes are bridged.
+    IERC20 public stakeToken;
+    // OFT that bridges stakes to Solana once Solana holds stakes in tokens
+    IStakeOFT public stakeOft;
+    // Winnings Solana issued that a recipient can withdraw from the escrow
+    mapping(address => uint256) public payouts;
+    // By the guid of the message that carried the action
+    mapping(bytes32 => ActionResult) public results;
+
     constructor(address _endpoint, address _delegate) OApp(_endpoint, _delegate) Ownable(_delegate) {}
 
-    // Send a Betlify Action to Solana OApp
-    function sendBetlifyAction(
+    // Sends a Betlify action to the Solana OApp on behalf of the caller. Solana attributes every
+    // action to the user address its body starts with, so the adapter writes msg.sender there itself
+    // and `args` is the rest of the body, e.g. abi.encodePacked(poolId, option, amount) for a PlaceBet.
+    function sendAction(
         uint32 dstEid,
-        bytes calldata message,
+        BetlifyMsgCodec.MsgType msgType,
+        bytes calldata args,
         bytes calldata optionsData
     ) external payable returns (MessagingReceipt memory receipt) {
+        uint64 requestId = nextRequestId++;
+        bytes memory message = _actionMessage(requestId, msgType, msg.sender, args);
+        _collectStake(msgType, args);
+        receipt = _send(dstEid, message, optionsData);
+        emit ActionSent(receipt.guid, requestId, msg.sender);
+    }
+
+    // Sends up to BetlifyMsgCodec.MAX_BATCH_LEN actions of the caller that Solana runs all or nothing
+    function sendBatch(
+        uint32 dstEid,
+        BetlifyMsgCodec.MsgType[] calldata msgTypes,
+        bytes[] calldata args,
+        bytes calldata optionsData
+    ) external payable returns (MessagingReceipt memory receipt) {
+        uint64 requestId = nextRequestId++;
+        bytes memory message = _batchMessage(requestId, msgTypes, msg.sender, args);
+        for (uint i = 0; i < msgTypes.length; i++) {
+            _collectStake(msgTypes[i], args[i]);
+        }
+        receipt = _send(dstEid, message, optionsData);
+        emit ActionSent(receipt.guid, requestId, msg.sender);
+    }
+
+    // Bets `amount` of the stake token on a Solana pool by bridging it through the stake OFT with the
+    // caller's PlaceBet composed onto it. Solana places the bet with what arrives, or sends the stake
+    // back through the OFT if the bet can't be placed. `extraOptions` must carry the compose gas.
+    function sendBetWithStake(
+        uint32 dstEid,
+        uint64 poolId,
+        uint8 option,
+        uint64 amount,
+        bytes calldata extraOptions
+    ) external payable returns (MessagingReceipt memory receipt) {
+        if (address(stakeOft) == address(0)) revert StakeOftNotSet();
+        uint64 requestId = nextRequestId++;
+        SendParam memory sendParam = _stakeSendParam(dstEid, requestId, poolId, option, amount, extraOptions);
+        stakeToken.safeTransferFrom(msg.sender, address(this), amount);
+        stakeToken.forceApprove(address(stakeOft), amount);
+        (receipt, ) = stakeOft.send{ value: msg.value }(sendParam, MessagingFee(msg.value, 0), msg.sender);
+        emit ActionSent(receipt.guid, requestId, msg.sender);
+    }
+
+    function quoteAction(
+        uint32 dstEid,
+        BetlifyMsgCodec.MsgType msgType,
+        bytes calldata args,
+        bytes calldata optionsData,
+        bool payInLzToken
+    ) external view returns (MessagingFee memory fee) {
+        bytes memory message = _actionMessage(nextRequestId, msgType, msg.sender, args);
+        return _quote(dstEid, message, combineOptions(dstEid, 1, optionsData), payInLzToken);
+    }
+
+    function quoteBatch(
+        uint32 dstEid,
+        BetlifyMsgCodec.MsgType[] calldata msgTypes,
+        bytes[] calldata args,
+        bytes calldata optionsData,
+        bool payInLzToken
+    ) external view returns (MessagingFee memory fee) {
+        bytes memory message = _batchMessage(nextRequestId, msgTypes, msg.sender, args);
+        return _quote(dstEid, message, combineOptions(dstEid, 1, optionsData), payInLzToken);
+    }
+
+    function quoteBetWithStake(
+        uint32 dstEid,
+        uint64 poolId,
+        uint8 option,
+        uint64 amount,
+        bytes calldata extraOptions,
+        bool payInLzToken
+    ) external view returns (MessagingFee memory fee) {
+        if (address(stakeOft) == address(0)) revert StakeOftNotSet();
+        SendParam memory sendParam = _stakeSendParam(dstEid, nextRequestId, poolId, option, amount, extraOptions);
+        return stakeOft.quoteSend(sendParam, payInLzToken);
+    }
+
+    function _send(
+        uint32 dstEid,
+        bytes memory message,
+        bytes calldata optionsData
+    ) internal returns (MessagingReceipt memory) {
         bytes memory lzOptions = combineOptions(dstEid, 1, optionsData);
-        receipt = _lzSend(dstEid, message, lzOptions, MessagingFee(msg.value, 0), payable(msg.sender));
+        return _lzSend(dstEid, message, lzOptions, MessagingFee(msg.value, 0), payable(msg.sender));
+    }
+
+    function _actionMessage(
+        uint64 requestId,
+        BetlifyMsgCodec.MsgType msgType,
+        address user,
+        bytes calldata args
+    ) internal view returns (bytes memory) {
+        _requireAction(msgType);
+        return
+            abi.encodePacked(
+                BetlifyMsgCodec.encodeHeader(msgType, requestId, uint64(block.timestamp)),
+                user,
+                args
+            );
+    }
+
+    function _batchMessage(
+        uint64 requestId,
+        BetlifyMsgCodec.MsgType[] calldata msgTypes,
+        address user,
+        bytes[] calldata args
+    ) internal view returns (bytes memory) {
+        if (msgTypes.length != args.length) revert BatchArgsMismatch(msgTypes.length, args.length);
+        bytes[] memory items = new bytes[](msgTypes.length);
+        for (uint i = 0; i < msgTypes.length; i++) {
+            _requireAction(msgTypes[i]);
+            items[i] = BetlifyMsgCodec.encodeBatchItem(msgTypes[i], abi.encodePacked(user, args[i]));
+        }
+        return BetlifyMsgCodec.encodeBatch(requestId, uint64(block.timestamp), items);
+    }
+
+    // The stake goes to the Solana OApp's Store, which the OFT hands the PlaceBet to. The OFT drops
+    // dust, so an amount with dust fails minAmountLD rather than betting less than asked.
+    function _stakeSendParam(
+        uint32 dstEid,
+        uint64 requestId,
+        uint64 poolId,
+        uint8 option,
+        uint64 amount,
+        bytes calldata extraOptions
+    ) internal view returns (SendParam memory) {
+        bytes memory placeBet = BetlifyMsgCodec.encodePlaceBet(
+            requestId,
+            uint64(block.timestamp),
+            msg.sender,
+            poolId,
+            option,
+            amount
+        );
+        return SendParam(dstEid, _getPeerOrRevert(dstEid), amount, amount, extraOptions, placeBet, "");
+    }
+
+    // Escrows the stake of a PlaceBet, whose args are poolId | option | amount
+    function _collectStake(BetlifyMsgCodec.MsgType msgType, bytes calldata args) internal {
+        if (msgType != BetlifyMsgCodec.MsgType.PlaceBet || address(stakeToken) == address(0)) return;
+        uint64 amount = uint64(bytes8(args[9:17]));
+        stakeToken.safeTransferFrom(msg.sender, address(this), amount);
+    }
+
+    function setStakeToken(IERC20 _stakeToken) external onlyOwner {
+        stakeToken = _stakeToken;
+    }
+
+    function setStakeOft(IStakeOFT _stakeOft) external onlyOwner {
+        stakeOft = _stakeOft;
+    }
+
+    // Sends the caller the winnings Solana issued to them
+    function withdrawPayout() external {
+        uint256 amount = payouts[msg.sender];
+        if (amount == 0) revert NothingToWithdraw();
+        payouts[msg.sender] = 0;
+        stakeToken.safeTransfer(msg.sender, amount);
+        emit PayoutWithdrawn(msg.sender, amount);
+    }
+
+    function _requireAction(BetlifyMsgCodec.MsgType msgType) internal pure {
+        if (
+            msgType != BetlifyMsgCodec.MsgType.CreatePool &&
+            msgType != BetlifyMsgCodec.MsgType.PlaceBet &&
+            msgType != BetlifyMsgCodec.MsgType.ResolveMarket &&
+            msgType != BetlifyMsgCodec.MsgType.ClaimWinnings &&
+            msgType != BetlifyMsgCodec.MsgType.LinkWallet
+        ) revert NotAnAction(msgType);
     }
 
-    // Handle incoming messages from Solana (stub)
+    // Handles Solana's responses to the actions sent from here. Payouts are credited rather than
+    // transferred, so a payout the escrow can't cover yet doesn't block the messages after it.
     function _lzReceive(
         Origin calldata /*_origin*/,
         bytes32 /*_guid*/,
-        bytes calldata /*payload*/,
+        bytes calldata payload,
         address /*_executor*/,
         bytes calldata /*_extraData*/
     ) internal override {
-        // TODO: handle Solana -> EVM messages (e.g., payout confirmations)
+        (BetlifyMsgCodec.MsgType msgType, uint64 requestId, ) = BetlifyMsgCodec.decodeHeader(payload);
+        if (msgType == BetlifyMsgCodec.MsgType.PayoutIssued) {
+            (uint64 poolId, bytes32 recipient, uint64 amount) = BetlifyMsgCodec.decodePayoutIssued(payload);
+            // Solana keeps EVM bettors left-padded to 32 bytes
+            address to = address(uint160(uint256(recipient)));
+            payouts[to] += amount;
+            emit PayoutIssued(requestId, poolId, to, amount);
+        } else if (msgType == BetlifyMsgCodec.MsgType.ActionAck) {
+            (bytes32 guid, uint8 status, uint64 poolId, bytes32 bet) = BetlifyMsgCodec.decodeActionAck(payload);
+            ActionResult storage result = results[guid];
+            (result.acked, result.status, result.poolId, result.bet) = (true, status, poolId, bet);
+            emit ActionAcked(guid, requestId, status, poolId, bet);
+        } else if (msgType == BetlifyMsgCodec.MsgType.MessageFailed) {
+            // Solana keeps the message and anyone can retry it there, so its stake stays escrowed
+            (bytes32 guid, uint32 errorCode) = BetlifyMsgCodec.decodeMessageFailed(payload);
+            results[guid].errorCode = errorCode;
+            emit ActionFailed(guid, requestId, errorCode);
+        } else {
+            revert NotAResponse(msgType);
+        }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.22;
+
+import { MessagingFee, MessagingReceipt } from "@layerzerolabs/oapp-evm/contracts/oapp/OAppSender.sol";
+
+// The parts of LayerZero's IOFT the adapter bridges stakes with, ABI compatible with it
+
+struct SendParam {
+    uint32 dstEid;
+    bytes32 to;
+    uint256 amountLD;
+    uint256 minAmountLD;
+    bytes extraOptions;
+    bytes composeMsg;
+    bytes oftCmd;
+}
+
+struct OFTReceipt {
+    uint256 amountSentLD;
+    uint256 amountReceivedLD;
+}
+
+interface IStakeOFT {
+    function quoteSend(SendParam calldata sendParam, bool payInLzToken) external view returns (MessagingFee memory);
+
+    function send(
+        SendParam calldata sendParam,
+        MessagingFee calldata fee,
+        address refundAddress
+    ) external payable returns (MessagingReceipt memory, OFTReceipt memory);
+}
@@ -2,11 +2,38 @@
 pragma solidity ^0.8.22;
 
 library BetlifyMsgCodec {
+    // Every message starts with: version | msgType | requestId | timestamp
+    uint8 internal constant VERSION = 1;
+    uint8 internal constant MAX_BATCH_LEN = 4;
+    uint256 internal constant HEADER_LEN = 18;
+
+    // ActionAck statuses
+    uint8 internal constant ACK_EXECUTED = 0;
+    uint8 internal constant ACK_FAILED = 1;
+
+    error InvalidBatchLength(uint256 length);
+    error NestedBatch();
+    error UnsupportedVersion(uint8 version);
+    error InvalidMessageLength(uint256 length);
+
     enum MsgType {
         CreatePool,
         PlaceBet,
         ResolveMarket,
-        ClaimWinnings
+        ClaimWinnings,
+        PayoutIssued,
+        Batch,
+        MessageFailed,
+        ActionAck,
+        LinkWallet
+    }
+
+    function encodeHeader(
+        MsgType msgType,
+        uint64 requestId,
+        uint64 timestamp
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(VERSION, uint8(msgType), requestId, timestamp);
     }
 
     function encodeString(string memory s) internal pure returns (bytes memory) {
@@ -22,57 +49,233 @@ library BetlifyMsgCodec {
         return out;
     }
 
+    // creatorPoolId is the creator's own id for the pool; Solana assigns the global pool id other
+    // messages use and reports it in the ActionAck. resolutionSource is where the outcome will be
+    // taken from, at most 128 bytes. Only the creator can resolve the pool.
     function encodeCreatePool(
+        uint64 requestId,
+        uint64 timestamp,
+        address creator,
         string memory question,
         string[] memory options,
         int64 startTime,
         int64 lockTime,
         int64 endTime,
-        uint64 poolId
+        uint64 creatorPoolId,
+        string memory resolutionSource
     ) internal pure returns (bytes memory) {
         return abi.encodePacked(
-            uint8(MsgType.CreatePool),
+            encodeHeader(MsgType.CreatePool, requestId, timestamp),
+            createPoolBody(creator, question, options, startTime, lockTime, endTime, creatorPoolId, resolutionSource)
+        );
+    }
+
+    function createPoolBody(
+        address creator,
+        string memory question,
+        string[] memory options,
+        int64 startTime,
+        int64 lockTime,
+        int64 endTime,
+        uint64 creatorPoolId,
+        string memory resolutionSource
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(
+            creator,
             encodeString(question),
             encodeStringArray(options),
             startTime,
             lockTime,
             endTime,
-            poolId
+            creatorPoolId,
+            encodeString(resolutionSource)
         );
     }
 
     function encodePlaceBet(
+        uint64 requestId,
+        uint64 timestamp,
         address authority,
         uint64 poolId,
         uint8 option,
         uint64 amount
     ) internal pure returns (bytes memory) {
         return abi.encodePacked(
-            uint8(MsgType.PlaceBet),
-            authority,
-            poolId,
-            option,
-            amount
+            encodeHeader(MsgType.PlaceBet, requestId, timestamp),
+            placeBetBody(authority, poolId, option, amount)
         );
     }
 
+    function placeBetBody(
+        address authority,
+        uint64 poolId,
+        uint8 option,
+        uint64 amount
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(authority, poolId, option, amount);
+    }
+
     function encodeResolveMarket(
+        uint64 requestId,
+        uint64 timestamp,
+        address resolver,
         uint64 poolId,
-        uint8 winningOption
+        uint8 winningOption,
+        string memory evidenceUri,
+        bytes32 evidenceHash
     ) internal pure returns (bytes memory) {
         return abi.encodePacked(
-            uint8(MsgType.ResolveMarket),
-            poolId,
-            winningOption
+            encodeHeader(MsgType.ResolveMarket, requestId, timestamp),
+            resolveMarketBody(resolver, poolId, winningOption, evidenceUri, evidenceHash)
         );
     }
 
+    function resolveMarketBody(
+        address resolver,
+        uint64 poolId,
+        uint8 winningOption,
+        string memory evidenceUri,
+        bytes32 evidenceHash
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(resolver, poolId, winningOption, encodeString(evidenceUri), evidenceHash);
+    }
+
     function encodeClaimWinnings(
+        uint64 requestId,
+        uint64 timestamp,
+        address authority,
         uint64 poolId
     ) internal pure returns (bytes memory) {
         return abi.encodePacked(
-            uint8(MsgType.ClaimWinnings),
-            poolId
+            encodeHeader(MsgType.ClaimWinnings, requestId, timestamp),
+            claimWinningsBody(authority, poolId)
+        );
+    }
+
+    function claimWinningsBody(address authority, uint64 poolId) internal pure returns (bytes memory) {
+        return abi.encodePacked(authority, poolId);
+    }
+
+    function encodeLinkWallet(
+        uint64 requestId,
+        uint64 timestamp,
+        address authority,
+        bytes32 solanaPubkey
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(
+            encodeHeader(MsgType.LinkWallet, requestId, timestamp),
+            linkWalletBody(authority, solanaPubkey)
         );
     }
+
+    function linkWalletBody(address authority, bytes32 solanaPubkey) internal pure returns (bytes memory) {
+        return abi.encodePacked(authority, solanaPubkey);
+    }
+
+    function encodePayoutIssued(
+        uint64 requestId,
+        uint64 timestamp,
+        uint64 poolId,
+        bytes32 recipient,
+        uint64 amount
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(
+            encodeHeader(MsgType.PayoutIssued, requestId, timestamp),
+            poolId,
+            recipient,
+            amount
+        );
+    }
+
+    function encodeMessageFailed(
+        uint64 requestId,
+        uint64 timestamp,
+        bytes32 guid,
+        uint32 errorCode
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(
+            encodeHeader(MsgType.MessageFailed, requestId, timestamp),
+            guid,
+            errorCode
+        );
+    }
+
+    function encodeActionAck(
+        uint64 requestId,
+        uint64 timestamp,
+        bytes32 guid,
+        uint8 status,
+        uint64 poolId,
+        bytes32 bet
+    ) internal pure returns (bytes memory) {
+        return abi.encodePacked(
+            encodeHeader(MsgType.ActionAck, requestId, timestamp),
+            guid,
+            status,
+            poolId,
+            bet
+        );
+    }
+
+    // A batch item is its type followed by one of the *Body encodings; items share the batch's header
+    function encodeBatchItem(MsgType msgType, bytes memory body) internal pure returns (bytes memory) {
+        if (msgType == MsgType.Batch) revert NestedBatch();
+        return abi.encodePacked(uint8(msgType), body);
+    }
+
+    // Items run in order on Solana and either all succeed or none do
+    function encodeBatch(
+        uint64 requestId,
+        uint64 timestamp,
+        bytes[] memory items
+    ) internal pure returns (bytes memory) {
+        if (items.length == 0 || items.length > MAX_BATCH_LEN) revert InvalidBatchLength(items.length);
+        bytes memory out = abi.encodePacked(encodeHeader(MsgType.Batch, requestId, timestamp), uint8(items.length));
+        for (uint i = 0; i < items.length; i++) {
+            out = abi.encodePacked(out, items[i]);
+        }
+        return out;
+    }
+
+    // Messages Solana sends back are decoded below. Like the Solana decoder, they reject anything
+    // but the exact packed length of their type.
+
+    function decodeHeader(
+        bytes calldata message
+    ) internal pure returns (MsgType msgType, uint64 requestId, uint64 timestamp) {
+        if (message.length < HEADER_LEN) revert InvalidMessageLength(message.length);
+        if (uint8(message[0]) != VERSION) revert UnsupportedVersion(uint8(message[0]));
+        msgType = MsgType(uint8(message[1]));
+        requestId = uint64(bytes8(message[2:10]));
+        timestamp = uint64(bytes8(message[10:18]));
+    }
+
+    function decodePayoutIssued(
+        bytes calldata message
+    ) internal pure returns (uint64 poolId, bytes32 recipient, uint64 amount) {
+        _requireLength(message, HEADER_LEN + 48);
+        poolId = uint64(bytes8(message[18:26]));
+        recipient = bytes32(message[26:58]);
+        amount = uint64(bytes8(message[58:66]));
+    }
+
+    function decodeMessageFailed(bytes calldata message) internal pure returns (bytes32 guid, uint32 errorCode) {
+        _requireLength(message, HEADER_LEN + 36);
+        guid = bytes32(message[18:50]);
+        errorCode = uint32(bytes4(message[50:54]));
+    }
+
+    function decodeActionAck(
+        bytes calldata message
+    ) internal pure returns (bytes32 guid, uint8 status, uint64 poolId, bytes32 bet) {
+        _requireLength(message, HEADER_LEN + 73);
+        guid = bytes32(message[18:50]);
+        status = uint8(message[50]);
+        poolId = uint64(bytes8(message[51:59]));
+        bet = bytes32(message[59:91]);
+    }
+
+    function _requireLength(bytes calldata message, uint256 length) private pure {
+        if (message.length != length) revert InvalidMessageLength(message.length);
+    }
 }
@@ -1,14 +1,20 @@
 import { ethers } from 'ethers';
 import { useToast } from '@/hooks/use-toast';
-import { encodeBetlifyMessage } from './encodeBetlifyMessage';
-import { BN } from '@project-serum/anchor';
+import { encodeActionArgs, MsgType, type BetlifyAction } from './encodeBetlifyMessage';
 // import { SolanaContractService } from './solana-contracts';
 
 // BetlifyOApp contract ABI - using the actual deployed contract
 const BETLIFY_OAPP_ABI = [
-  "function sendBetlifyAction(uint32 dstEid, bytes calldata message, bytes calldata optionsData) external payable returns (tuple(bytes32 guid, uint64 nonce, bytes32 receipt))",
-  "function quoteSend(uint32 dstEid, bytes calldata message, bytes calldata optionsData) external view returns (uint256 nativeFee, uint256 lzTokenFee)",
-  "event MessagingReceipt(bytes32 indexed guid, uint64 indexed nonce, bytes32 receipt)"
+  "function sendAction(uint32 dstEid, uint8 msgType, bytes calldata args, bytes calldata optionsData) external payable returns (tuple(bytes32 guid, uint64 nonce, tuple(uint256 nativeFee, uint256 lzTokenFee) fee) receipt)",
+  "function quoteAction(uint32 dstEid, uint8 msgType, bytes calldata args, bytes calldata optionsData, bool payInLzToken) external view returns (tuple(uint256 nativeFee, uint256 lzTokenFee) fee)",
+  "function results(bytes32 guid) external view returns (bool acked, uint8 status, uint64 poolId, bytes32 bet, uint32 errorCode)",
+  "function stakeToken() external view returns (address)",
+  "event ActionSent(bytes32 indexed guid, uint64 requestId, address indexed user)"
+];
+
+const ERC20_ABI = [
+  "function allowance(address owner, address spender) external view returns (uint256)",
+  "function approve(address spender, uint256 amount) external returns (bool)"
 ];
 
 // Contract addresses from deployment
@@ -32,13 +38,30 @@ const NETWORKS = {
   },
 };
 
-const encodeMessage = (message: any) => {
-  return encodeBetlifyMessage(message);
-};
-
 // LayerZero destination EID for Solana
 const SOLANA_DST_EID = 40168;
 
+// No extra executor options; the adapter combines these with the enforced options
+const OPTIONS_DATA = '0x';
+
+// The CreatePool action for a binary market. creatorPoolId only has to be unique among the
+// creator's pools, so the creation time is used; Solana assigns the global pool id.
+const createPoolAction = (marketData: {
+  title: string;
+  startTime: Date;
+  lockTime: Date;
+  resolutionDate: Date;
+}): BetlifyAction => ({
+  msgType: MsgType.CreatePool,
+  question: marketData.title,
+  options: ['Yes', 'No'],
+  startTime: BigInt(Math.floor(marketData.startTime.getTime() / 1000)),
+  lockTime: BigInt(Math.floor(marketData.lockTime.getTime() / 1000)),
+  endTime: BigInt(Math.floor(marketData.resolutionDate.getTime() / 1000)),
+  creatorPoolId: BigInt(Date.now()),
+  resolutionSource: '',
+});
+
 export class BetlifyContractService {
   private provider: ethers.BrowserProvider | null = null;
   private signer: ethers.Signer | null = null;
@@ -239,28 +262,7 @@ export class BetlifyContractService {
     }
 
     try {
-      // For binary markets, we'll use Yes/No options
-      const options = ['Yes', 'No'];
-      
-      // Encode the create pool message
-      const message = encodeBetlifyMessage({
-        variant: 0, // CreatePool
-        question: marketData.title,
-        options: options,
-        pool_id: new BN(1), // poolId - we'll use 1 for now, in production this should be generated
-        start_time: new BN(Math.floor(marketData.startTime.getTime() / 1000)),
-        lock_time: new BN(Math.floor(marketData.lockTime.getTime() / 1000)),
-        end_time: new BN(Math.floor(marketData.resolutionDate.getTime() / 1000)),
-      });
-
-      // Calculate rent for Solana PDA (simplified)
-      const optionsData = '0x0000000000000000'; // 8 bytes of zeros for rent
-
-      const [nativeFee, lzTokenFee] = await this.contract.quoteSend(
-        SOLANA_DST_EID,
-        message,
-        optionsData
-      );
+      const { nativeFee, lzTokenFee } = await this.quoteAction(createPoolAction(marketData));
       
       console.log('Quote result:', {
         nativeFee: nativeFee.toString(),
@@ -283,7 +285,6 @@ export class BetlifyContractService {
     resolutionDate: Date;
     creatorFee: string;
     minimumBet: string;
-    poolId: number;
   }): Promise<string> {
     console.log('📦 [ContractService] Entering createPool...');
     
@@ -298,25 +299,9 @@ export class BetlifyContractService {
       const signerAddress = await this.signer.getAddress();
       console.log('📦 [ContractService] Signer address:', signerAddress);
 
-      // For binary markets, we'll use Yes/No options
-      const options = ['Yes', 'No'];
-      
-      // Encode the create pool message
-      const message = encodeBetlifyMessage({
-        variant: 0, // CreatePool
-        question: marketData.title,
-        options: options,
-        pool_id: new BN(marketData.poolId),
-        start_time: new BN(Math.floor(marketData.startTime.getTime() / 1000)),
-        lock_time: new BN(Math.floor(marketData.lockTime.getTime() / 1000)),
-        end_time: new BN(Math.floor(marketData.resolutionDate.getTime() / 1000)),
-      });
-
-      console.log('📦 [ContractService] Encoded message length:', message.length, 'bytes');
-      console.log('📦 [ContractService] Encoded message (hex):', message.toString('hex'));
-
-      // Calculate rent for Solana PDA (simplified)
-      const optionsData = '0x'; // 8 bytes of zeros for rent
+      const action = createPoolAction(marketData);
+      const { nativeFee } = await this.quoteAction(action);
+      console.log('📦 [ContractService] Messaging fee:', ethers.formatEther(nativeFee), 'ETH');
       
       // Check if user has sufficient balance
       const balance = await this.provider!.getBalance(signerAddress);
@@ -324,14 +309,7 @@ export class BetlifyContractService {
       
       // Send the cross-chain message with estimated gas
       console.log('📦 [ContractService] Sending transaction...');
-      const tx = await this.contract.sendBetlifyAction(
-        SOLANA_DST_EID,
-        message,
-        optionsData,
-        {
-          value: ethers.parseEther('0.001'),
-        }
-      );
+      const tx = await this.sendAction(action, nativeFee);
 
       console.log('📦 [ContractService] Transaction sent:', tx.hash);
 
@@ -351,6 +329,9 @@ export class BetlifyContractService {
       
       console.log('📦 [ContractService] Transaction confirmed:', receipt);
 
+      // Solana reports the pool's global id in its ack for this guid, see getActionResult
+      console.log('📦 [ContractService] Action guid:', this.actionGuid(receipt));
+
       this.safeToast({
         title: "Bet Pool Created!",
         description: `Successfully sent create pool message to Solana via LayerZero`,
@@ -408,6 +389,67 @@ export class BetlifyContractService {
     }
   }
 
+  // Quote the LayerZero fee for sending `action` to Solana
+  private async quoteAction(action: BetlifyAction): Promise<{ nativeFee: bigint; lzTokenFee: bigint }> {
+    const fee = await this.contract!.quoteAction(
+      SOLANA_DST_EID,
+      action.msgType,
+      encodeActionArgs(action),
+      OPTIONS_DATA,
+      false
+    );
+    return { nativeFee: fee.nativeFee, lzTokenFee: fee.lzTokenFee };
+  }
+
+  // Send `action` to Solana, paying `nativeFee` for the message
+  private async sendAction(action: BetlifyAction, nativeFee: bigint): Promise<ethers.ContractTransactionResponse> {
+    return await this.contract!.sendAction(
+      SOLANA_DST_EID,
+      action.msgType,
+      encodeActionArgs(action),
+      OPTIONS_DATA,
+      { value: nativeFee }
+    );
+  }
+
+  // The guid of the action a confirmed transaction sent, from its ActionSent event
+  private actionGuid(receipt: any): string | null {
+    for (const log of receipt?.logs ?? []) {
+      const parsed = this.contract!.interface.parseLog(log);
+      if (parsed?.name === 'ActionSent') return parsed.args.guid;
+    }
+    return null;
+  }
+
+  // What Solana reported back for the action sent with `guid`. poolId is the global pool id of a
+  // created pool once acked.
+  async getActionResult(guid: string): Promise<{ acked: boolean; status: number; poolId: bigint; errorCode: number }> {
+    if (!this.contract) {
+      throw new Error('Contract not initialized');
+    }
+    const result = await this.contract.results(guid);
+    return {
+      acked: result.acked,
+      status: Number(result.status),
+      poolId: result.poolId,
+      errorCode: Number(result.errorCode),
+    };
+  }
+
+  // The adapter escrows PlaceBet stakes in its stake token, if it has one, so it needs an allowance
+  private async approveStake(amount: bigint): Promise<void> {
+    const stakeToken: string = await this.contract!.stakeToken();
+    if (stakeToken === ethers.ZeroAddress) return;
+
+    const token = new ethers.Contract(stakeToken, ERC20_ABI, this.signer);
+    const owner = await this.signer!.getAddress();
+    const allowance: bigint = await token.allowance(owner, CONTRACT_ADDRESSES.optimismTestnet);
+    if (allowance < amount) {
+      const tx = await token.approve(CONTRACT_ADDRESSES.optimismTestnet, amount);
+      await tx.wait();
+    }
+  }
+
   // Place a bet
   async placeBet(betData: {
     poolId: string;
@@ -420,39 +462,24 @@ export class BetlifyContractService {
     }
 
     try {
-      // Create the message for placing a bet
-      const message = encodeBetlifyMessage({
-        variant: 1, // PlaceBet variant
-        pool_id: new BN(parseInt(betData.poolId) || 0),
+      // poolId is the global pool id Solana assigned
+      if (!/^\d+$/.test(betData.poolId)) {
+        throw new Error(`Not a Solana pool id: ${betData.poolId}`);
+      }
+      const action: BetlifyAction = {
+        msgType: MsgType.PlaceBet,
+        poolId: BigInt(betData.poolId),
         option: betData.option === 'yes' ? 0 : 1, // Convert string to number (0 for yes, 1 for no)
-        amount: new BN(Math.floor(parseFloat(betData.amount) * 1e6)), // Convert to USDC units
-      });
+        amount: BigInt(Math.floor(parseFloat(betData.amount) * 1e6)), // Convert to USDC units
+      };
 
-      console.log('📦 [ContractService] Place bet message:', message);
+      console.log('📦 [ContractService] Place bet args:', encodeActionArgs(action));
 
-      // Calculate rent for Solana PDA
-      const optionsData = '0x';
-      
-      // Estimate gas for the transaction
-      console.log('📦 [ContractService] Estimating gas for place bet transaction...');
-      const gasEstimate = await this.contract.sendBetlifyAction.estimateGas(
-        SOLANA_DST_EID,
-        message,
-        optionsData
-      );
-      
-      console.log('📦 [ContractService] Gas estimate for place bet:', gasEstimate.toString());
-      
+      await this.approveStake(action.amount);
+      const { nativeFee } = await this.quoteAction(action);
 
       // Send the transaction
-      const tx = await this.contract.sendBetlifyAction(
-        SOLANA_DST_EID,
-        message,
-        optionsData,
-        {
-          value: ethers.parseEther('0.001'),
-        }
-      );
+      const tx = await this.sendAction(action, nativeFee);
 
       console.log('📦 [ContractService] Place bet transaction sent:', tx.hash);
       
@@ -1,99 +1,98 @@
-import * as borsh from "@coral-xyz/borsh";
-import BN from "bn.js";
+import { ethers } from "ethers";
 
-// Define your message types to match the Rust enum
-export type BetlifyMessage =
-  | { variant: 0; question: string; options: string[]; pool_id: BN; start_time: BN; lock_time: BN; end_time: BN }
-  | { variant: 1; pool_id: BN; option: number; amount: BN }
-  | { variant: 2; pool_id: BN; winning_option: number }
-  | { variant: 3; pool_id: BN };
+// Mirrors contracts/libs/BetlifyMsgCodec.sol: messages are packed big-endian, and every message
+// starts with version | msgType | requestId | timestamp.
+export const BETLIFY_MSG_VERSION = 1;
 
-// Create Borsh schemas for each variant
-const createPoolSchema = borsh.struct([
-  borsh.u8("variant"),
-  borsh.str("question"),
-  borsh.vec(borsh.str(), "options"),
-  borsh.u64("pool_id"),
-  borsh.i64("start_time"),
-  borsh.i64("lock_time"),
-  borsh.i64("end_time"),
-]);
-
-const placeBetSchema = borsh.struct([
-  borsh.u8("variant"),
-  borsh.u64("pool_id"),
-  borsh.u8("option"),
-  borsh.u64("amount"),
-]);
+export enum MsgType {
+  CreatePool = 0,
+  PlaceBet = 1,
+  ResolveMarket = 2,
+  ClaimWinnings = 3,
+  PayoutIssued = 4,
+  Batch = 5,
+  MessageFailed = 6,
+  ActionAck = 7,
+  LinkWallet = 8,
+}
 
-const resolveMarketSchema = borsh.struct([
-  borsh.u8("variant"),
-  borsh.u64("pool_id"),
-  borsh.u8("winning_option"),
-]);
+// The actions a user sends. Pools are addressed by the global pool id Solana assigns, except
+// CreatePool, which carries the creator's own id for the pool; Solana reports the global id back in
+// its ActionAck.
+export type BetlifyAction =
+  | {
+      msgType: MsgType.CreatePool;
+      question: string;
+      options: string[];
+      startTime: bigint;
+      lockTime: bigint;
+      endTime: bigint;
+      creatorPoolId: bigint;
+      resolutionSource: string;
+    }
+  | { msgType: MsgType.PlaceBet; poolId: bigint; option: number; amount: bigint }
+  | {
+      msgType: MsgType.ResolveMarket;
+      poolId: bigint;
+      winningOption: number;
+      evidenceUri: string;
+      evidenceHash: string;
+    }
+  | { msgType: MsgType.ClaimWinnings; poolId: bigint }
+  | { msgType: MsgType.LinkWallet; solanaPubkey: string };
 
-const claimWinningsSchema = borsh.struct([
-  borsh.u8("variant"),
-  borsh.u64("pool_id"),
-]);
+function encodeString(s: string): string {
+  const bytes = ethers.toUtf8Bytes(s);
+  return ethers.solidityPacked(["uint32", "bytes"], [bytes.length, bytes]);
+}
 
-// Helper function to convert BN to number for u64/i64 fields
-function bnToNumber(bn: BN): BN {
-  return bn;
+function encodeStringArray(arr: string[]): string {
+  return ethers.concat([ethers.solidityPacked(["uint32"], [arr.length]), ...arr.map(encodeString)]);
 }
 
-// Encode a message using Borsh
-export function encodeBetlifyMessage(message: BetlifyMessage): Buffer {
-  let schema: any;
-  let data: any;
-  
-  switch (message.variant) {
-    case 0: // CreatePool
-      schema = createPoolSchema;
-      data = {
-        variant: 0,
-        question: message.question,
-        options: message.options,
-        pool_id: message.pool_id,
-        start_time: message.start_time,
-        lock_time: message.lock_time,
-        end_time: message.end_time,
-      };
-      break;
-      
-    case 1: // PlaceBet
-      schema = placeBetSchema;
-      data = {
-        variant: 1,
-        pool_id: message.pool_id,
-        option: message.option,
-        amount: message.amount,
-      };
-      break;
-      
-    case 2: // ResolveMarket
-      schema = resolveMarketSchema;
-      data = {
-        variant: 2,
-        pool_id: message.pool_id,
-        winning_option: message.winning_option,
-      };
-      break;
-      
-    case 3: // ClaimWinnings
-      schema = claimWinningsSchema;
-      data = {
-        variant: 3,
-        pool_id: message.pool_id,
-      };
-      break;
-      
+// The body of `action` without the user address it starts with. This is what BetlifyOApp.sendAction
+// takes as `args`: the adapter writes the sender's address in front of it and adds the header.
+export function encodeActionArgs(action: BetlifyAction): string {
+  switch (action.msgType) {
+    case MsgType.CreatePool:
+      return ethers.concat([
+        encodeString(action.question),
+        encodeStringArray(action.options),
+        ethers.solidityPacked(
+          ["int64", "int64", "int64", "uint64"],
+          [action.startTime, action.lockTime, action.endTime, action.creatorPoolId],
+        ),
+        encodeString(action.resolutionSource),
+      ]);
+    case MsgType.PlaceBet:
+      return ethers.solidityPacked(["uint64", "uint8", "uint64"], [action.poolId, action.option, action.amount]);
+    case MsgType.ResolveMarket:
+      return ethers.concat([
+        ethers.solidityPacked(["uint64", "uint8"], [action.poolId, action.winningOption]),
+        encodeString(action.evidenceUri),
+        ethers.solidityPacked(["bytes32"], [action.evidenceHash]),
+      ]);
+    case MsgType.ClaimWinnings:
+      return ethers.solidityPacked(["uint64"], [action.poolId]);
+    case MsgType.LinkWallet:
+      return ethers.solidityPacked(["bytes32"], [action.solanaPubkey]);
     default:
-      throw new Error(`Unknown variant`);
+      throw new Error(`Unknown message type`);
   }
-  
-  // Allocate buffer and encode
-  const buffer = Buffer.alloc(1000); // Allocate generous buffer
-  schema.encode(data, buffer);
-  return buffer.subarray(0, schema.getSpan(buffer));
-}
\ No newline at end of file
+}
+
+// The full message the adapter sends for `action` of `user`, e.g. to check what Solana receives
+export function encodeBetlifyMessage(
+  requestId: bigint,
+  timestamp: bigint,
+  user: string,
+  action: BetlifyAction,
+): string {
+  return ethers.concat([
+    ethers.solidityPacked(
+      ["uint8", "uint8", "uint64", "uint64", "address"],
+      [BETLIFY_MSG_VERSION, action.msgType, requestId, timestamp, user],
+    ),
+    encodeActionArgs(action),
+  ]);
+}
@@ -144,7 +144,7 @@ export default function BetDetails() {
         }
 
         const betData = {
-          poolId: betDetails?.id || "1",
+          poolId: betDetails?.id ?? "",
           option: selectedOption,
           amount: betAmount,
         };
@@ -164,7 +164,6 @@ export default function CreateMarket() {
         resolutionDate: new Date(data.resolutionDate),
         creatorFee: data.creatorFee || "1.0",
         minimumBet: data.minimumBet || "1.0",
-        poolId:2
       };
 
       // Create pool via contract
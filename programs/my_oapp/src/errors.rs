@@ -1,6 +1,13 @@
 use anchor_lang::prelude::error_code;
 
-#[error_code]
+// Every error enum of the program has its own offset, 100 apart, so an error code on its own (like
+// the one a FailedMessage keeps) names a single error. New enums take the next free hundred.
+#[error_code(offset = 6000)]
 pub enum MyOAppError {
-    InvalidMessageType
+    InvalidMessageType,
+    InvalidNonce,
+    Paused,
+    PeerPaused,
+    #[msg("Stake token accounts are required once stakes are held in tokens")]
+    MissingStakeAccounts,
 }
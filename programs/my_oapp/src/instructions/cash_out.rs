@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::instructions::claim_winnings::pay_from_pool_vault;
+use crate::state::bet_pool::BetPool;
+use crate::state::bet::Bet;
+use crate::state::IdentityLink;
+use crate::state::store::Store;
+use crate::errors::MyOAppError;
+use crate::{POOL_VAULT_SEED, STORE_SEED};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CashOutParams {
+    // No parameters needed for this instruction
+}
+
+// Withdraws a bet from a pool that is still taking bets, returning the whole stake. The bet stays
+// settled afterwards, so its owner can't bet on the pool again.
+#[derive(Accounts)]
+pub struct CashOut<'info> {
+    #[account(mut)]
+    pub bet_pool: Account<'info, BetPool>,
+    #[account(mut)]
+    pub bet: Account<'info, Bet>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    /// Link of the bet's remote user, when a linked Solana key manages a cross-chain bet
+    pub identity_link: Option<Account<'info, IdentityLink>>,
+    /// Vault holding the pool's stakes, required once stakes are held in tokens
+    #[account(mut, seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()], bump)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    /// Stake token account the stake is returned to
+    #[account(mut)]
+    pub user_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code(offset = 7700)]
+pub enum CashOutError {
+    #[msg("Betting is closed, the bet can no longer be cashed out")]
+    BettingClosed,
+    #[msg("Already cashed out or settled")]
+    AlreadySettled,
+    #[msg("Invalid bet for this pool")]
+    InvalidBet,
+    #[msg("Bet does not belong to this user")]
+    InvalidAuthority,
+    #[msg("Stake is held on the bet's source chain, cash it out from there")]
+    CashOutOnSourceChain,
+}
+
+impl<'info> CashOut<'info> {
+    pub fn apply(ctx: &mut Context<Self>, _params: &CashOutParams) -> Result<()> {
+        // A cash-out only hands a stake back, so it is let through a pause like a refund
+        require!(ctx.accounts.store.refunds_open(), MyOAppError::Paused);
+        let identity_link = ctx.accounts.identity_link.as_deref();
+        require!(
+            ctx.accounts.bet.is_managed_by(ctx.accounts.user.key(), identity_link),
+            CashOutError::InvalidAuthority
+        );
+        // Cross-chain stakes only reach Solana in tokens, otherwise the source chain holds them
+        require!(
+            ctx.accounts.bet.origin_eid == 0 || ctx.accounts.store.stake_mint != Pubkey::default(),
+            CashOutError::CashOutOnSourceChain
+        );
+        let pool_key = ctx.accounts.bet_pool.key();
+        let clock = Clock::get()?;
+        let stake =
+            settle_cash_out(&mut ctx.accounts.bet_pool, pool_key, &mut ctx.accounts.bet, clock.unix_timestamp)?;
+        pay_from_pool_vault(
+            &ctx.accounts.store,
+            ctx.accounts.pool_vault.as_ref(),
+            ctx.accounts.user_token.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            stake,
+        )
+    }
+}
+
+// Takes the bet back out of the pool totals record_bet added it to, marks it settled and returns the
+// stake owed back to the bettor
+pub fn settle_cash_out(pool: &mut BetPool, pool_key: Pubkey, bet: &mut Bet, now: i64) -> Result<u64> {
+    pool.sync_status(now);
+    require!(pool.is_accepting_bets(now), CashOutError::BettingClosed);
+    require_keys_eq!(bet.pool, pool_key, CashOutError::InvalidBet);
+    require!(!bet.claimed, CashOutError::AlreadySettled);
+
+    let option_amount = pool.option_amounts.get_mut(bet.option as usize).ok_or(CashOutError::InvalidBet)?;
+    *option_amount = option_amount.checked_sub(bet.amount).ok_or(CashOutError::InvalidBet)?;
+    pool.pool_amount = pool.pool_amount.checked_sub(bet.amount).ok_or(CashOutError::InvalidBet)?;
+    pool.unique_bettors = pool.unique_bettors.saturating_sub(1);
+
+    bet.claimed = true;
+    Ok(bet.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::bet_pool::PoolStatus;
+    use crate::test_utils::*;
+
+    const USER: [u8; 32] = [4; 32];
+    const LINKED_KEY: Pubkey = Pubkey::new_from_array([5; 32]);
+
+    // Cashes out `bet` from an open pool with 100 staked on each option, returning the pool and bet
+    fn cash_out(store: &Store, user: Pubkey, bet: Bet, identity_link: Option<IdentityLink>) -> Result<(BetPool, Bet)> {
+        let pool_key = bet.pool;
+        let identity_link = identity_link
+            .map(|link| load(program_account(Pubkey::new_unique(), &link, 8 + IdentityLink::INIT_SPACE)));
+        let mut accounts = CashOut {
+            bet_pool: load(program_account(pool_key, &bet_pool(PoolStatus::Open), 8 + BetPool::INIT_SPACE)),
+            bet: load(program_account(Pubkey::new_unique(), &bet, 8 + Bet::INIT_SPACE)),
+            user: signer(user),
+            store: store_account(store),
+            identity_link,
+            pool_vault: None,
+            user_token: None,
+            token_program: None,
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        CashOut::apply(&mut ctx, &CashOutParams {})?;
+        Ok(((*accounts.bet_pool).clone(), (*accounts.bet).clone()))
+    }
+
+    #[test]
+    fn cashing_out_takes_the_stake_out_of_the_pool() {
+        set_now(0);
+        let user = Pubkey::new_unique();
+        let (pool, bet) = cash_out(&store(), user, bet(user, Pubkey::new_unique()), None).unwrap();
+        assert_eq!((pool.pool_amount, pool.option_amounts), (100, vec![100, 0]));
+        assert_eq!(pool.unique_bettors, 1);
+        assert!(bet.claimed);
+
+        let err = cash_out(&store(), user, bet, None).unwrap_err();
+        assert_eq!(err, CashOutError::AlreadySettled.into());
+    }
+
+    #[test]
+    fn bets_are_locked_in_once_betting_closes() {
+        set_now(1);
+        let user = Pubkey::new_unique();
+        let err = cash_out(&store(), user, bet(user, Pubkey::new_unique()), None).unwrap_err();
+        assert_eq!(err, CashOutError::BettingClosed.into());
+    }
+
+    #[test]
+    fn the_linked_key_cashes_out_a_cross_chain_bet() {
+        set_now(0);
+        let store = Store { stake_mint: Pubkey::new_unique(), ..store() };
+        let remote_bet = Bet { user: USER, origin_eid: SRC_EID, ..bet(Pubkey::default(), Pubkey::new_unique()) };
+        let err = cash_out(&store, LINKED_KEY, remote_bet.clone(), None).unwrap_err();
+        assert_eq!(err, CashOutError::InvalidAuthority.into());
+
+        // Authorized, the stake is paid out of the pool vault
+        let link = IdentityLink { src_eid: SRC_EID, user: USER, solana_pubkey: LINKED_KEY, bump: 0 };
+        let err = cash_out(&store, LINKED_KEY, remote_bet, Some(link)).unwrap_err();
+        assert_eq!(err, MyOAppError::MissingStakeAccounts.into());
+    }
+}
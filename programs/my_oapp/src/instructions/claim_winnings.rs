@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::bet_pool::{BetPool, PoolStatus};
 use crate::state::bet::Bet;
+use crate::state::IdentityLink;
+use crate::state::store::Store;
+use crate::errors::MyOAppError;
+use crate::{POOL_VAULT_SEED, STORE_SEED};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct ClaimWinningsParams {
@@ -15,10 +20,21 @@ pub struct ClaimWinnings<'info> {
     pub bet: Account<'info, Bet>,
     #[account(mut)]
     pub user: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    /// Link of the bet's remote user, when a linked Solana key manages a cross-chain bet
+    pub identity_link: Option<Account<'info, IdentityLink>>,
+    /// Vault holding the pool's stakes, required once stakes are held in tokens
+    #[account(mut, seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()], bump)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    /// Stake token account the winnings are paid to
+    #[account(mut)]
+    pub user_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
     pub system_program: Program<'info, System>,
 }
 
-#[error_code]
+#[error_code(offset = 6600)]
 pub enum ClaimWinningsError {
     #[msg("Result not set")] 
     ResultNotSet,
@@ -28,25 +44,157 @@ pub enum ClaimWinningsError {
     AlreadyClaimed,
     #[msg("Invalid bet for this pool")] 
     InvalidBet,
+    #[msg("Bet does not belong to this user")] 
+    Unauthorized,
+    #[msg("Payout can't be computed")] 
+    InvalidPayout,
+    #[msg("Stake is held on the bet's source chain, claim it from there")] 
+    ClaimOnSourceChain,
 }
 
 impl<'info> ClaimWinnings<'info> {
     pub fn apply(ctx: &mut Context<Self>, _params: &ClaimWinningsParams) -> Result<()> {
-        let pool = &ctx.accounts.bet_pool;
-        let bet = &mut ctx.accounts.bet;
+        require!(ctx.accounts.store.claims_open(), MyOAppError::Paused);
+        let identity_link = ctx.accounts.identity_link.as_deref();
+        require!(
+            ctx.accounts.bet.is_managed_by(ctx.accounts.user.key(), identity_link),
+            ClaimWinningsError::Unauthorized
+        );
+        // Cross-chain stakes only reach Solana in tokens, otherwise the source chain pays them out
+        require!(
+            ctx.accounts.bet.origin_eid == 0 || ctx.accounts.store.stake_mint != Pubkey::default(),
+            ClaimWinningsError::ClaimOnSourceChain
+        );
+        let pool_key = ctx.accounts.bet_pool.key();
+        let payout = settle_claim(&ctx.accounts.bet_pool, pool_key, &mut ctx.accounts.bet)?;
+        pay_from_pool_vault(
+            &ctx.accounts.store,
+            ctx.accounts.pool_vault.as_ref(),
+            ctx.accounts.user_token.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            payout,
+        )
+    }
+}
+
+// Shared by claim_winnings, refund_bet and cash_out. Pays `amount` of the stake token out of the pool vault,
+// with the Store signing as the vault authority. Without a stake mint no stakes are held to pay out.
+pub fn pay_from_pool_vault<'info>(
+    store: &Account<'info, Store>,
+    pool_vault: Option<&Account<'info, TokenAccount>>,
+    to: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    amount: u64,
+) -> Result<()> {
+    if store.stake_mint == Pubkey::default() || amount == 0 {
+        return Ok(());
+    }
+    let (Some(pool_vault), Some(to), Some(token_program)) = (pool_vault, to, token_program) else {
+        return err!(MyOAppError::MissingStakeAccounts);
+    };
+    let seeds: &[&[u8]] = &[STORE_SEED, &[store.bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: pool_vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: store.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
+}
 
-        // Validate pool is resolved
-        require!(pool.is_result_set, ClaimWinningsError::ResultNotSet);
-        require!(pool.status == PoolStatus::Resolved, ClaimWinningsError::MarketNotResolved);
+// Shared by claim_winnings and the ClaimWinnings branch of lz_receive.
+// Marks the bet as claimed and returns the amount owed to the bettor.
+pub fn settle_claim(pool: &BetPool, pool_key: Pubkey, bet: &mut Bet) -> Result<u64> {
+    // Validate pool is resolved
+    require!(pool.is_result_set, ClaimWinningsError::ResultNotSet);
+    require!(pool.status == PoolStatus::Resolved, ClaimWinningsError::MarketNotResolved);
 
-        // Validate bet belongs to this pool
-        require_keys_eq!(bet.pool, pool.key(), ClaimWinningsError::InvalidBet);
+    // Validate bet belongs to this pool
+    require_keys_eq!(bet.pool, pool_key, ClaimWinningsError::InvalidBet);
 
-        // Check if bet is already claimed
-        require!(!bet.claimed, ClaimWinningsError::AlreadyClaimed);
+    // Check if bet is already claimed
+    require!(!bet.claimed, ClaimWinningsError::AlreadyClaimed);
 
-        // Mark as claimed (actual payout logic would go here)
-        bet.claimed = true;
-        Ok(())
+    bet.claimed = true;
+
+    // Nobody backed the outcome, so there are no winners to split the pool between. Every bettor
+    // gets their stake back instead, like on a voided pool.
+    let winning_total = pool.option_amounts[pool.winning_option as usize];
+    if winning_total == 0 {
+        return Ok(bet.amount);
+    }
+    if bet.option != pool.winning_option {
+        return Ok(0);
     }
-} 
\ No newline at end of file
+    // Winners split the whole pool pro rata to their stake on the winning option
+    let payout = (bet.amount as u128)
+        .checked_mul(pool.pool_amount as u128)
+        .and_then(|share| share.checked_div(winning_total as u128))
+        .and_then(|payout| u64::try_from(payout).ok())
+        .ok_or(ClaimWinningsError::InvalidPayout)?;
+    Ok(payout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const USER: [u8; 32] = [4; 32];
+    const LINKED_KEY: Pubkey = Pubkey::new_from_array([5; 32]);
+
+    // USER's bet placed from SRC_EID, on a pool resolved in its favour
+    fn remote_bet(pool: Pubkey) -> Bet {
+        Bet { authority: Pubkey::default(), user: USER, origin_eid: SRC_EID, ..bet(Pubkey::default(), pool) }
+    }
+
+    fn link(src_eid: u32) -> IdentityLink {
+        IdentityLink { src_eid, user: USER, solana_pubkey: LINKED_KEY, bump: 0 }
+    }
+
+    fn claim(store: &Store, user: Pubkey, identity_link: Option<IdentityLink>) -> Result<()> {
+        let pool_key = Pubkey::new_unique();
+        let identity_link = identity_link
+            .map(|link| load(program_account(Pubkey::new_unique(), &link, 8 + IdentityLink::INIT_SPACE)));
+        let mut accounts = ClaimWinnings {
+            bet_pool: load(program_account(pool_key, &bet_pool(PoolStatus::Resolved), 8 + BetPool::INIT_SPACE)),
+            bet: load(program_account(Pubkey::new_unique(), &remote_bet(pool_key), 8 + Bet::INIT_SPACE)),
+            user: signer(user),
+            store: store_account(store),
+            identity_link,
+            pool_vault: None,
+            user_token: None,
+            token_program: None,
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        ClaimWinnings::apply(&mut ctx, &ClaimWinningsParams {})
+    }
+
+    #[test]
+    fn only_the_linked_key_manages_a_cross_chain_bet() {
+        let store = Store { stake_mint: Pubkey::new_unique(), ..store() };
+        let err = claim(&store, LINKED_KEY, None).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::Unauthorized.into());
+        let err = claim(&store, Pubkey::new_unique(), Some(link(SRC_EID))).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::Unauthorized.into());
+        // A link is per source chain
+        let err = claim(&store, LINKED_KEY, Some(link(SRC_EID + 1))).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::Unauthorized.into());
+
+        // Authorized, the claim pays out of the pool vault
+        let err = claim(&store, LINKED_KEY, Some(link(SRC_EID))).unwrap_err();
+        assert_eq!(err, MyOAppError::MissingStakeAccounts.into());
+    }
+
+    #[test]
+    fn cross_chain_bets_without_token_stakes_are_claimed_on_their_source_chain() {
+        let err = claim(&store(), LINKED_KEY, Some(link(SRC_EID))).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::ClaimOnSourceChain.into());
+    }
+}
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use crate::state::bet_pool::{BetPool, PoolStatus};
+use crate::state::store::Store;
+use crate::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CloseBettingParams {
+    // No parameters needed for this instruction
+}
+
+// Permissionless crank: anyone can close betting once lock_time has passed and collect the keeper reward
+#[derive(Accounts)]
+pub struct CloseBetting<'info> {
+    #[account(
+        mut,
+        seeds = [BET_POOL_SEED, store.key().as_ref(), &bet_pool.id.to_le_bytes()],
+        bump = bet_pool.bump
+    )]
+    pub bet_pool: Account<'info, BetPool>,
+    #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+}
+
+#[error_code(offset = 6900)]
+pub enum CloseBettingError {
+    #[msg("Lock time not reached")] 
+    LockTimeNotReached,
+    #[msg("Pool is not open")] 
+    PoolNotOpen,
+}
+
+impl<'info> CloseBetting<'info> {
+    pub fn apply(ctx: &mut Context<Self>, _params: &CloseBettingParams) -> Result<()> {
+        let pool = &mut ctx.accounts.bet_pool;
+        let clock = Clock::get()?;
+
+        require!(pool.status == PoolStatus::Open, CloseBettingError::PoolNotOpen);
+        require!(clock.unix_timestamp >= pool.lock_time, CloseBettingError::LockTimeNotReached);
+
+        pool.status = PoolStatus::Closed;
+
+        // Pools nobody bet on don't need closing, and rewarding them would pay for throwaway pools
+        if pool.unique_bettors == 0 {
+            return Ok(());
+        }
+
+        // Pay the keeper from the Store's spare lamports; skip silently if the treasury can't cover it
+        let store_info = ctx.accounts.store.to_account_info();
+        let rent_exempt = Rent::get()?.minimum_balance(store_info.data_len());
+        let reward = ctx.accounts.store.keeper_reward;
+        if reward > 0 && store_info.lamports().saturating_sub(rent_exempt) >= reward {
+            **store_info.try_borrow_mut_lamports()? -= reward;
+            **ctx.accounts.keeper.to_account_info().try_borrow_mut_lamports()? += reward;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const KEEPER: Pubkey = Pubkey::new_from_array([6; 32]);
+    const REWARD: u64 = 5_000;
+
+    // Cranks `pool` at `now` with a Store holding `spare` lamports over its rent. Returns the pool and
+    // the reward the keeper got.
+    fn close(pool: BetPool, now: i64, spare: u64) -> Result<(BetPool, u64)> {
+        set_now(now);
+        let store_state = Store { keeper_reward: REWARD, ..store() };
+        let mut data = vec![0; Store::SIZE];
+        store_state.try_serialize(&mut &mut data[..]).unwrap();
+        let lamports = Rent::default().minimum_balance(Store::SIZE) + spare;
+        let mut accounts = CloseBetting {
+            bet_pool: load(program_account(Pubkey::new_unique(), &pool, 8 + BetPool::INIT_SPACE)),
+            store: load(account_info(store_key(), crate::ID, lamports, data)),
+            keeper: signer(KEEPER),
+        };
+        let before = accounts.keeper.lamports();
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        CloseBetting::apply(&mut ctx, &CloseBettingParams {})?;
+        Ok(((*accounts.bet_pool).clone(), accounts.keeper.lamports() - before))
+    }
+
+    #[test]
+    fn betting_closes_once_the_lock_time_passes() {
+        let pool = bet_pool(PoolStatus::Open);
+        let err = close(pool.clone(), pool.lock_time - 1, REWARD).unwrap_err();
+        assert_eq!(err, CloseBettingError::LockTimeNotReached.into());
+
+        let (closed, reward) = close(pool.clone(), pool.lock_time, REWARD).unwrap();
+        assert!(closed.status == PoolStatus::Closed);
+        assert_eq!(reward, REWARD);
+
+        let err = close(closed, pool.lock_time, REWARD).unwrap_err();
+        assert_eq!(err, CloseBettingError::PoolNotOpen.into());
+    }
+
+    #[test]
+    fn pools_without_bets_close_without_a_reward() {
+        let pool = BetPool { unique_bettors: 0, pool_amount: 0, option_amounts: vec![0, 0], ..bet_pool(PoolStatus::Open) };
+        let (closed, reward) = close(pool, 1, REWARD).unwrap();
+        assert!(closed.status == PoolStatus::Closed);
+        assert_eq!(reward, 0);
+    }
+
+    #[test]
+    fn rewards_are_skipped_when_the_store_cannot_cover_them() {
+        let (closed, reward) = close(bet_pool(PoolStatus::Open), 1, REWARD - 1).unwrap();
+        assert!(closed.status == PoolStatus::Closed);
+        assert_eq!(reward, 0);
+    }
+}
@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
-use crate::state::bet_pool::{BetPool, PoolStatus};
+use crate::state::bet_pool::{
+    BetPool, PoolStatus, MAX_OPTIONS, MAX_QUESTION_LEN, MAX_RESOLUTION_SOURCE_LEN, MIN_OPTIONS,
+};
 use crate::state::store::Store;
 use crate::*;
+use crate::errors::MyOAppError;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct CreatePoolParams {
@@ -10,47 +13,207 @@ pub struct CreatePoolParams {
     pub start_time: i64,
     pub lock_time: i64,
     pub end_time: i64,
-    pub pool_id: u64,
+    pub creator_pool_id: u64, // The creator's own id for the pool, see CreatorPoolIndex
+    pub resolution_source: String,
 }
 
 #[derive(Accounts)]
 #[instruction(params: CreatePoolParams)]
 pub struct CreatePool<'info> {
+    // Local and cross-chain pools share one id space, allocated from the Store's pool count. The pool
+    // may exist already: a CreatePool that failed in lz_receive for a non-blocking peer leaves the
+    // empty pool it created at the next id.
     #[account(
         init_if_needed,
         payer = creator,
-        seeds = [b"betpool", creator.key().as_ref(), &params.pool_id.to_le_bytes()],
+        seeds = [BET_POOL_SEED, store.key().as_ref(), &store.pools_count.to_le_bytes()],
         bump,
-        space = BetPool::INIT_SPACE,  //8 + 32 + 4 + 256 + 4 + (32 * 8) + 1 + 1 + (8 * 8) + 1 + 8*3 + 8 + 4 + (8*8) + 1 + 1 + 1
+        space = 8 + BetPool::INIT_SPACE,
     )]
     pub bet_pool: Account<'info, BetPool>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [
+            CREATOR_POOL_INDEX_SEED,
+            store.key().as_ref(),
+            &0u32.to_be_bytes(),
+            creator.key().as_ref(),
+            &params.creator_pool_id.to_le_bytes()
+        ],
+        bump,
+        space = 8 + CreatorPoolIndex::INIT_SPACE
+    )]
+    pub creator_pool_index: Account<'info, CreatorPoolIndex>,
     #[account(mut)]
     pub creator: Signer<'info>,
-    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
     pub store: Account<'info, Store>,
     pub system_program: Program<'info, System>,
 }
 
+#[error_code(offset = 6300)]
+pub enum CreatePoolError {
+    #[msg("Resolution source too long")] 
+    ResolutionSourceTooLong,
+    #[msg("Invalid timing")] 
+    InvalidTiming,
+    #[msg("End time must be in the future")] 
+    EndTimeInPast,
+    #[msg("Pool duration too short")] 
+    DurationTooShort,
+    #[msg("Pool duration too long")] 
+    DurationTooLong,
+    #[msg("Question is empty")] 
+    QuestionEmpty,
+    #[msg("Question too long")] 
+    QuestionTooLong,
+    #[msg("Invalid number of options")] 
+    InvalidOptionCount,
+    #[msg("No pool ids left")] 
+    TooManyPools,
+    #[msg("Pool id already in use")] 
+    PoolIdInUse,
+}
+
+// Shared by create_pool, update_pool_schedule and the CreatePool branch of lz_receive
+pub fn validate_pool_schedule(store: &Store, start_time: i64, lock_time: i64, end_time: i64, now: i64) -> Result<()> {
+    require!(start_time <= lock_time && lock_time <= end_time, CreatePoolError::InvalidTiming);
+    require!(end_time > now, CreatePoolError::EndTimeInPast);
+    // Times too far apart to subtract are as invalid as misordered ones
+    let duration = end_time.checked_sub(start_time).ok_or(CreatePoolError::InvalidTiming)?;
+    require!(duration >= store.min_pool_duration, CreatePoolError::DurationTooShort);
+    require!(duration <= store.max_pool_duration, CreatePoolError::DurationTooLong);
+    Ok(())
+}
+
+// When a pool ending at `end_time` can be voided if unresolved. Shared like validate_pool_schedule.
+pub fn resolution_deadline(end_time: i64) -> Result<i64> {
+    Ok(end_time.checked_add(BetPool::RESOLUTION_WINDOW).ok_or(CreatePoolError::InvalidTiming)?)
+}
+
+// Shared by create_pool and the CreatePool branch of lz_receive
+pub fn validate_pool_params(
+    store: &Store,
+    question: &str,
+    options: &[String],
+    start_time: i64,
+    lock_time: i64,
+    end_time: i64,
+    now: i64,
+) -> Result<()> {
+    require!(!question.is_empty(), CreatePoolError::QuestionEmpty);
+    require!(question.len() <= MAX_QUESTION_LEN, CreatePoolError::QuestionTooLong);
+    require!((MIN_OPTIONS..=MAX_OPTIONS).contains(&options.len()), CreatePoolError::InvalidOptionCount);
+    validate_pool_schedule(store, start_time, lock_time, end_time, now)
+}
+
 impl<'info> CreatePool<'info> {
-    pub fn apply(ctx: &mut Context<Self>, params: &CreatePoolParams, pool_id: u64) -> Result<()> {
+    pub fn apply(ctx: &mut Context<Self>, params: &CreatePoolParams) -> Result<()> {
+        require!(!ctx.accounts.store.paused, MyOAppError::Paused);
+        let clock = Clock::get()?;
+        validate_pool_params(
+            &ctx.accounts.store,
+            &params.question,
+            &params.options,
+            params.start_time,
+            params.lock_time,
+            params.end_time,
+            clock.unix_timestamp,
+        )?;
+        require!(params.resolution_source.len() <= MAX_RESOLUTION_SOURCE_LEN, CreatePoolError::ResolutionSourceTooLong);
+
         let bet_pool = &mut ctx.accounts.bet_pool;
+        // Every created pool has options, so only a pool left empty by a failed message is reused
+        require!(bet_pool.option_amounts.is_empty(), CreatePoolError::PoolIdInUse);
         let store = &mut ctx.accounts.store;
-        
+        let pool_id = store.pools_count;
+
         bet_pool.id = pool_id;
         bet_pool.creator = ctx.accounts.creator.key();
+        bet_pool.origin_eid = 0;
+        bet_pool.remote_creator = [0; 32];
         bet_pool.question = params.question.clone();
         bet_pool.status = PoolStatus::Open;
         bet_pool.winning_option = 0; // Default to first option
         bet_pool.start_time = params.start_time;
         bet_pool.lock_time = params.lock_time;
         bet_pool.end_time = params.end_time;
+        bet_pool.resolution_deadline = resolution_deadline(params.end_time)?;
         bet_pool.unique_bettors = 0;
         bet_pool.pool_amount = 0;
         bet_pool.option_amounts = vec![0; params.options.len()];
         bet_pool.is_result_set = false;
         bet_pool.result = 0; // Default to first option
+        bet_pool.resolution_source = params.resolution_source.clone();
+        bet_pool.evidence_uri = String::new();
+        bet_pool.evidence_hash = [0; 32];
+        bet_pool.bump = ctx.bumps.bet_pool;
 
-        store.pools_count = store.pools_count.checked_add(1).unwrap();
+        let creator_pool_index = &mut ctx.accounts.creator_pool_index;
+        creator_pool_index.origin_eid = 0;
+        creator_pool_index.creator = ctx.accounts.creator.key().to_bytes();
+        creator_pool_index.creator_pool_id = params.creator_pool_id;
+        creator_pool_index.pool_id = pool_id;
+        creator_pool_index.bump = ctx.bumps.creator_pool_index;
+
+        store.pools_count = store.pools_count.checked_add(1).ok_or(CreatePoolError::TooManyPools)?;
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use anchor_lang::Discriminator;
+
+    fn params() -> CreatePoolParams {
+        CreatePoolParams {
+            question: "Who will win?".to_string(),
+            options: vec!["Yes".to_string(), "No".to_string()],
+            start_time: NOW,
+            lock_time: NOW + 60 * 60,
+            end_time: NOW + 2 * 60 * 60,
+            creator_pool_id: 1,
+            resolution_source: String::new(),
+        }
+    }
+
+    // An account just created for `T`: its discriminator followed by zeroes
+    fn created<T: Discriminator>(space: usize) -> AccountInfo<'static> {
+        let mut data = vec![0; space];
+        data[..8].copy_from_slice(&T::discriminator());
+        account_info(Pubkey::new_unique(), crate::ID, Rent::default().minimum_balance(space), data)
+    }
+
+    // Creates a pool in place of `existing`, returning it and the Store's pool count after
+    fn create_pool(existing: AccountInfo<'static>) -> Result<(BetPool, u64)> {
+        set_now(NOW);
+        let mut accounts = CreatePool {
+            bet_pool: load(existing),
+            creator_pool_index: load(created::<CreatorPoolIndex>(8 + CreatorPoolIndex::INIT_SPACE)),
+            creator: signer(Pubkey::new_unique()),
+            store: store_account(&Store { pools_count: 3, ..store() }),
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        CreatePool::apply(&mut ctx, &params())?;
+        Ok(((*accounts.bet_pool).clone(), accounts.store.pools_count))
+    }
+
+    #[test]
+    fn pools_left_empty_by_failed_messages_are_reused() {
+        let (pool, pools_count) = create_pool(created::<BetPool>(8 + BetPool::INIT_SPACE)).unwrap();
+        assert_eq!((pool.id, pool.option_amounts), (3, vec![0, 0]));
+        assert_eq!(pools_count, 4);
+    }
+
+    #[test]
+    fn existing_pools_are_not_overwritten() {
+        let pool = bet_pool(PoolStatus::Open);
+        let existing = program_account(Pubkey::new_unique(), &pool, 8 + BetPool::INIT_SPACE);
+        let err = create_pool(existing).unwrap_err();
+        assert_eq!(err, CreatePoolError::PoolIdInUse.into());
+    }
+}
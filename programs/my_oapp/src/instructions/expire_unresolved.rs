@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::bet_pool::{BetPool, PoolStatus};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ExpireUnresolvedParams {
+    // No parameters needed for this instruction
+}
+
+// Permissionless: anyone can void a pool whose creator missed the resolution deadline
+#[derive(Accounts)]
+pub struct ExpireUnresolved<'info> {
+    #[account(mut)]
+    pub bet_pool: Account<'info, BetPool>,
+}
+
+#[error_code(offset = 6800)]
+pub enum ExpireUnresolvedError {
+    #[msg("Resolution deadline not reached")] 
+    DeadlineNotReached,
+    #[msg("Result already set")] 
+    ResultAlreadySet,
+    #[msg("Pool already voided")] 
+    AlreadyVoided,
+}
+
+impl<'info> ExpireUnresolved<'info> {
+    pub fn apply(ctx: &mut Context<Self>, _params: &ExpireUnresolvedParams) -> Result<()> {
+        let pool = &mut ctx.accounts.bet_pool;
+        let clock = Clock::get()?;
+        pool.sync_status(clock.unix_timestamp);
+
+        require!(clock.unix_timestamp >= pool.resolution_deadline, ExpireUnresolvedError::DeadlineNotReached);
+        require!(!pool.is_result_set, ExpireUnresolvedError::ResultAlreadySet);
+        require!(pool.status != PoolStatus::Voided, ExpireUnresolvedError::AlreadyVoided);
+
+        // Voided pools let every bettor reclaim their stake through refund_bet, or a ClaimWinnings
+        // message for bets placed cross-chain
+        pool.status = PoolStatus::Voided;
+        Ok(())
+    }
+}
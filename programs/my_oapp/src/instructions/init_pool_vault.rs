@@ -0,0 +1,35 @@
+use crate::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::state::bet_pool::BetPool;
+
+// Creates the token account that holds a pool's cross-chain stakes. Anyone can pay for it.
+// The Store PDA is the vault authority, so only this program can move the tokens out.
+
+#[derive(Accounts)]
+pub struct InitPoolVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    pub bet_pool: Account<'info, BetPool>,
+    #[account(address = store.stake_mint)]
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = store,
+    )]
+    pub pool_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl InitPoolVault<'_> {
+    pub fn apply(_ctx: &mut Context<InitPoolVault>) -> Result<()> {
+        Ok(())
+    }
+}
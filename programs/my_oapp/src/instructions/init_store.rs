@@ -24,6 +24,22 @@ pub struct InitStore<'info> {
         bump
     )]
     pub lz_receive_types_accounts: Account<'info, LzReceiveTypesAccounts>,
+    #[account(
+        init,
+        payer = payer,
+        space = LzComposeTypesAccounts::SIZE,
+        seeds = [LZ_COMPOSE_TYPES_SEED, &store.key().to_bytes()],
+        bump
+    )]
+    pub lz_compose_types_accounts: Account<'info, LzComposeTypesAccounts>,
+    #[account(
+        init,
+        payer = payer,
+        space = ResponseAccounts::space(&[]),
+        seeds = [RESPONSE_ACCOUNTS_SEED, &store.key().to_bytes()],
+        bump
+    )]
+    pub response_accounts: Account<'info, ResponseAccounts>,
     pub system_program: Program<'info, System>,
 }
 
@@ -36,10 +52,26 @@ impl InitStore<'_> {
         ctx.accounts.store.bump = ctx.bumps.store;
         ctx.accounts.store.endpoint_program = params.endpoint;
         ctx.accounts.lz_receive_types_accounts.store = ctx.accounts.store.key();
+        ctx.accounts.lz_compose_types_accounts.store = ctx.accounts.store.key();
         // the above lines are required for all OApp implementations
 
+        ctx.accounts.lz_receive_types_accounts.response_accounts = ctx.accounts.response_accounts.key();
+        ctx.accounts.lz_compose_types_accounts.response_accounts = ctx.accounts.response_accounts.key();
+        ctx.accounts.response_accounts.bump = ctx.bumps.response_accounts;
+
         // the line below is specific to this string-passing example
         ctx.accounts.store.pools_count = 0;
+        ctx.accounts.store.keeper_reward = 0;
+        ctx.accounts.store.min_pool_duration = Store::DEFAULT_MIN_POOL_DURATION;
+        ctx.accounts.store.max_pool_duration = Store::DEFAULT_MAX_POOL_DURATION;
+        ctx.accounts.store.stake_mint = Pubkey::default();
+        ctx.accounts.store.oft_program = Pubkey::default();
+        ctx.accounts.store.oft_store = Pubkey::default();
+        ctx.accounts.store.stake_ld2sd_rate = 1;
+        ctx.accounts.store.pauser = params.admin;
+        ctx.accounts.store.paused = false;
+        ctx.accounts.store.claims_while_paused = false;
+        ctx.accounts.store.refunds_while_paused = false;
 
         // Prepare the delegate address for the OApp registration.
         let register_params = RegisterOAppParams { delegate: ctx.accounts.store.admin };
@@ -0,0 +1,390 @@
+use crate::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use oapp::{
+    endpoint::{
+        cpi::accounts::ClearCompose, instructions::ClearComposeParams, ConstructCPIContext, ID as ENDPOINT_ID,
+    },
+    LzComposeParams,
+};
+
+use crate::errors::MyOAppError;
+use crate::state::{bet_pool::BetPool, bet::Bet};
+
+// Handles stakes bridged through the OFT: the OFT program mints/unlocks the tokens to the Store and
+// forwards a compose message whose payload is the Betlify PlaceBet to execute with them.
+// The compose is cleared before the bet is placed, so a bet that can't be placed doesn't revert:
+// its stake is sent back to the bettor through the OFT instead. The OFT send accounts for that follow
+// the Endpoint::clear_compose accounts in `remaining_accounts`.
+
+#[derive(Accounts)]
+#[instruction(params: LzComposeParams)]
+pub struct LzCompose<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [STORE_SEED],
+        bump = store.bump,
+        constraint = params.from == store.oft_store @ LzComposeError::InvalidComposeSender
+    )]
+    pub store: Account<'info, Store>,
+
+    /// Peer of the chain the stake came from; checked in `apply` against the decoded src_eid.
+    /// Composed bets are charged to its rate limits.
+    #[account(mut)]
+    pub peer: Account<'info, PeerConfig>,
+
+    /// CHECK: Checked against the PDA of the pool the PlaceBet names, and loaded in `apply` so a
+    /// pool that doesn't exist gets the stake refunded
+    #[account(mut)]
+    pub bet_pool: UncheckedAccount<'info>,
+
+    // Keyed by the end user named in the PlaceBet, like bets placed through lz_receive
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [
+            BET_SEED,
+            oft::compose_src_eid(&params.message),
+            &msg_codec::message_user(oft::compose_payload(&params.message)),
+            bet_pool.key().as_ref()
+        ],
+        bump,
+        space = 8 + Bet::INIT_SPACE
+    )]
+    pub bet: Account<'info, Bet>,
+
+    /// Store-owned token account the OFT delivered the stake to
+    #[account(
+        mut,
+        constraint = stake_vault.mint == store.stake_mint,
+        constraint = stake_vault.owner == store.key()
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Loaded in `apply`, so a pool without a vault gets the stake refunded
+    #[account(mut, seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()], bump)]
+    pub pool_vault: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> LzCompose<'info> {
+    pub fn apply(ctx: &mut Context<'_, '_, 'info, 'info, Self>, params: &LzComposeParams) -> Result<()> {
+        require!(
+            ctx.remaining_accounts.len() >= ClearCompose::MIN_ACCOUNTS_LEN,
+            LzComposeError::MissingClearComposeAccounts
+        );
+        let (clear_accounts, refund_accounts) = ctx.remaining_accounts.split_at(ClearCompose::MIN_ACCOUNTS_LEN);
+        let seeds: &[&[u8]] = &[STORE_SEED, &[ctx.accounts.store.bump]];
+
+        oapp::endpoint_cpi::clear_compose(
+            ENDPOINT_ID,
+            ctx.accounts.store.key(),
+            clear_accounts,
+            seeds,
+            ClearComposeParams {
+                from: params.from,
+                guid: params.guid,
+                index: params.index,
+                message: params.message.clone(),
+            },
+        )?;
+
+        // Paused composes revert so they can be retried once the pause is lifted
+        require!(!ctx.accounts.store.paused, MyOAppError::Paused);
+
+        let compose = oft::decode_compose_msg(&params.message).map_err(|err| {
+            msg!("Compose decode error: {:?}", err);
+            LzComposeError::InvalidComposeMsg
+        })?;
+
+        // Only our own adapter on a configured peer chain may stake through the OFT
+        let store_key = ctx.accounts.store.key();
+        let (peer, _) = pda::peer(&store_key, compose.src_eid).address(ctx.program_id);
+        require_keys_eq!(ctx.accounts.peer.key(), peer, LzComposeError::InvalidPeer);
+        require!(ctx.accounts.peer.peer_address == compose.compose_from, LzComposeError::InvalidPeer);
+        require!(!ctx.accounts.peer.paused, MyOAppError::PeerPaused);
+        require!(compose.amount_ld > 0, LzComposeError::EmptyStake);
+        let bet_pool = composed_pool(ctx.program_id, store_key, &compose);
+        require_keys_eq!(ctx.accounts.bet_pool.key(), bet_pool, BetlifyError::InvalidPoolAccount);
+
+        let clock = Clock::get()?;
+        let placed = place_composed_bet(
+            &mut ctx.accounts.peer,
+            &ctx.accounts.bet_pool.to_account_info(),
+            &ctx.accounts.pool_vault.to_account_info(),
+            ctx.accounts.store.stake_mint,
+            &mut ctx.accounts.bet,
+            &compose,
+            clock.unix_timestamp,
+        );
+        let pool = match placed {
+            Ok(pool) => pool,
+            Err(err) => {
+                msg!("Refunding the stake of OFT nonce {} from eid {}: {}", compose.nonce, compose.src_eid, err);
+                let stake_vault = ctx.accounts.stake_vault.key();
+                return refund_stake(&ctx.accounts.store, &ctx.accounts.peer, stake_vault, refund_accounts, &compose);
+            }
+        };
+        msg!("Placed funded bet on pool {} (OFT nonce {})", pool.id, compose.nonce);
+        pool.exit(ctx.program_id)?;
+        ctx.accounts.bet.bump = ctx.bumps.bet;
+
+        // The stake is what actually arrived, not what the message claims
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.pool_vault.to_account_info(),
+                    authority: ctx.accounts.store.to_account_info(),
+                },
+                &[seeds],
+            ),
+            compose.amount_ld,
+        )
+    }
+}
+
+// The pool account lz_compose takes for a compose message: the pool its PlaceBet names. Other
+// payloads are refunded without touching the pool, and take pool 0.
+pub fn composed_pool(program_id: &Pubkey, store: Pubkey, compose: &oft::OftComposeMsg) -> Pubkey {
+    let pool_id = match msg_codec::decode_betlify_message(&compose.compose_msg) {
+        Ok(msg_codec::BetlifyMessage::PlaceBet { pool_id, .. }) => pool_id,
+        _ => 0,
+    };
+    pda::bet_pool(&store, pool_id).address(program_id).0
+}
+
+// Places the PlaceBet a compose message carries with the stake that arrived, charging it to the
+// peer's rate limits like lz_receive does. Returns the pool for the caller to persist; any error
+// means the stake is refunded.
+pub fn place_composed_bet<'info>(
+    peer: &mut PeerConfig,
+    pool_info: &AccountInfo<'info>,
+    pool_vault_info: &AccountInfo<'info>,
+    stake_mint: Pubkey,
+    bet: &mut Bet,
+    compose: &oft::OftComposeMsg,
+    now: i64,
+) -> Result<Account<'info, BetPool>> {
+    let (authority, pool_id, option) = match msg_codec::decode_betlify_message(&compose.compose_msg) {
+        Ok(msg_codec::BetlifyMessage::PlaceBet { authority, pool_id, option, .. }) => (authority, pool_id, option),
+        Ok(_) => return Err(MyOAppError::InvalidMessageType.into()),
+        Err(err) => {
+            msg!("Decode error: {}", err);
+            return Err(err.into());
+        },
+    };
+    if !peer.allows(msg_codec::PLACE_BET_TYPE) {
+        msg!("Rejected message type {} from eid {}", msg_codec::PLACE_BET_TYPE, compose.src_eid);
+        return Err(BetlifyError::MessageTypeNotAllowed.into());
+    }
+
+    let mut pool = Account::<BetPool>::try_from(pool_info).map_err(|_| BetlifyError::MissingPoolAccount)?;
+    require!(pool.id == pool_id, BetlifyError::InvalidPoolAccount);
+    let pool_vault =
+        Account::<TokenAccount>::try_from(pool_vault_info).map_err(|_| LzComposeError::MissingPoolVault)?;
+    require_keys_eq!(pool_vault.mint, stake_mint, LzComposeError::MissingPoolVault);
+    peer.consume_inbound(1, compose.amount_ld, now)?;
+
+    let pool_key = pool.key();
+    record_bet(&mut pool, pool_key, bet, option, compose.amount_ld, now)?;
+    bet.user = msg_codec::remote_user(authority);
+    bet.origin_eid = compose.src_eid;
+    Ok(pool)
+}
+
+// Who a stake that couldn't be bet goes back to: the bettor the PlaceBet names, or the adapter that
+// composed it if the payload names nobody
+pub fn refund_recipient(compose: &oft::OftComposeMsg) -> [u8; 32] {
+    let user = msg_codec::message_user(&compose.compose_msg);
+    if user == [0; 32] { compose.compose_from } else { user }
+}
+
+// Sends a stake back to its source chain through the OFT it arrived with. It arrived through the OFT,
+// so it is already free of dust. The OFT send accounts are (peer, oft_store, token_source, ...), with
+// the stake vault as the token source. Without them the compose reverts and can be retried once the
+// OFT send accounts for the source chain are registered.
+fn refund_stake<'info>(
+    store: &Account<'info, Store>,
+    peer: &PeerConfig,
+    stake_vault: Pubkey,
+    oft_accounts: &[AccountInfo<'info>],
+    compose: &oft::OftComposeMsg,
+) -> Result<()> {
+    require!(oft_accounts.len() > 2, LzComposeError::InvalidRefundAccounts);
+    require_keys_eq!(oft_accounts[1].key(), store.oft_store, LzComposeError::InvalidRefundAccounts);
+    require_keys_eq!(oft_accounts[2].key(), stake_vault, LzComposeError::InvalidRefundAccounts);
+
+    let seeds: &[&[u8]] = &[STORE_SEED, &[store.bump]];
+    oft::send(
+        store.oft_program,
+        store.to_account_info(),
+        oft_accounts,
+        seeds,
+        oft::OftSendParams {
+            dst_eid: compose.src_eid,
+            to: refund_recipient(compose),
+            amount_ld: compose.amount_ld,
+            min_amount_ld: compose.amount_ld,
+            options: Vec::new(),
+            compose_msg: None,
+            native_fee: peer.response_fee,
+            lz_token_fee: 0,
+        },
+    )
+}
+
+#[error_code(offset = 7100)]
+pub enum LzComposeError {
+    #[msg("Compose message not sent by the stake OFT")] InvalidComposeSender,
+    #[msg("Malformed OFT compose message")] InvalidComposeMsg,
+    #[msg("Stake did not come from a configured peer")] InvalidPeer,
+    #[msg("Stake amount is zero")] EmptyStake,
+    #[msg("Missing accounts for Endpoint::clear_compose")] MissingClearComposeAccounts,
+    #[msg("Pool has no vault for the stake token")] MissingPoolVault,
+    #[msg("Invalid OFT send accounts for the refund")] InvalidRefundAccounts,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, AccountState};
+
+    const ADAPTER: [u8; 32] = [7; 32];
+    const BETTOR: [u8; 20] = [1; 20];
+    const STAKE_MINT: Pubkey = Pubkey::new_from_array([4; 32]);
+
+    fn compose(payload: &msg_codec::BetlifyMessage, amount_ld: u64) -> oft::OftComposeMsg {
+        oft::OftComposeMsg {
+            nonce: 1,
+            src_eid: SRC_EID,
+            amount_ld,
+            compose_from: ADAPTER,
+            compose_msg: encoded(payload),
+        }
+    }
+
+    fn pool_vault(mint: Pubkey) -> AccountInfo<'static> {
+        let mut data = vec![0; SplTokenAccount::LEN];
+        let vault = SplTokenAccount { mint, state: AccountState::Initialized, ..SplTokenAccount::default() };
+        vault.pack_into_slice(&mut data);
+        account_info(Pubkey::new_unique(), token::ID, 1, data)
+    }
+
+    fn pool_info(pool: &BetPool) -> AccountInfo<'static> {
+        program_account(Pubkey::new_unique(), pool, 8 + BetPool::INIT_SPACE)
+    }
+
+    // Places `payload` with `amount_ld` of stake on an open pool 0 with a vault for the stake mint
+    fn place(
+        peer: &mut PeerConfig,
+        bet: &mut Bet,
+        payload: &msg_codec::BetlifyMessage,
+        amount_ld: u64,
+    ) -> Result<BetPool> {
+        let (pool, vault) = (pool_info(&bet_pool(PoolStatus::Open)), pool_vault(STAKE_MINT));
+        let pool = place_composed_bet(peer, &pool, &vault, STAKE_MINT, bet, &compose(payload, amount_ld), 0)?;
+        Ok((*pool).clone())
+    }
+
+    fn empty_bet() -> Bet {
+        bet(Pubkey::default(), Pubkey::default())
+    }
+
+    #[test]
+    fn bets_are_placed_with_the_stake_that_arrived() {
+        let mut bet = empty_bet();
+        let pool = place(&mut peer(ADAPTER), &mut bet, &place_bet(BETTOR, 0, 0, 1000), 500).unwrap();
+        assert_eq!((pool.pool_amount, pool.option_amounts.clone()), (700, vec![600, 100]));
+        assert_eq!(pool.unique_bettors, 3);
+        assert_eq!((bet.option, bet.amount), (0, 500));
+        assert_eq!((bet.user, bet.origin_eid), (msg_codec::remote_user(BETTOR), SRC_EID));
+        assert_eq!(bet.authority, Pubkey::default());
+
+        // A second stake for the same pool is refunded rather than added to the bet
+        let err = place(&mut peer(ADAPTER), &mut bet, &place_bet(BETTOR, 0, 1, 1000), 500).unwrap_err();
+        assert_eq!(err, PlaceBetError::DuplicateBet.into());
+        assert_eq!((bet.option, bet.amount), (0, 500));
+    }
+
+    #[test]
+    fn bets_that_cannot_be_placed_are_refunded() {
+        let mut bet = empty_bet();
+        let claim = claim(BETTOR, 0);
+        let err = place(&mut peer(ADAPTER), &mut bet, &claim, 500).unwrap_err();
+        assert_eq!(err, MyOAppError::InvalidMessageType.into());
+
+        let mut bets_not_allowed = PeerConfig {
+            allowed_msg_types: ALL_MSG_TYPES & !(1 << msg_codec::PLACE_BET_TYPE),
+            ..peer(ADAPTER)
+        };
+        let err = place(&mut bets_not_allowed, &mut bet, &place_bet(BETTOR, 0, 0, 1000), 500).unwrap_err();
+        assert_eq!(err, BetlifyError::MessageTypeNotAllowed.into());
+
+        let err = place(&mut peer(ADAPTER), &mut bet, &place_bet(BETTOR, 1, 0, 1000), 500).unwrap_err();
+        assert_eq!(err, BetlifyError::InvalidPoolAccount.into());
+        let err = place(&mut peer(ADAPTER), &mut bet, &place_bet(BETTOR, 0, 2, 1000), 500).unwrap_err();
+        assert_eq!(err, PlaceBetError::InvalidOption.into());
+        assert_eq!(bet.pool, Pubkey::default());
+    }
+
+    #[test]
+    fn stakes_for_missing_or_closed_pools_are_refunded() {
+        let compose = compose(&place_bet(BETTOR, 0, 0, 1000), 500);
+        let place_on = |pool: &AccountInfo<'static>, vault: &AccountInfo<'static>, now| {
+            let mut bet = empty_bet();
+            place_composed_bet(&mut peer(ADAPTER), pool, vault, STAKE_MINT, &mut bet, &compose, now).map(|_| ())
+        };
+        let missing = || account_info(Pubkey::new_unique(), anchor_lang::system_program::ID, 0, Vec::new());
+        let (pool, vault) = (pool_info(&bet_pool(PoolStatus::Open)), pool_vault(STAKE_MINT));
+
+        assert_eq!(place_on(&missing(), &vault, 0).unwrap_err(), BetlifyError::MissingPoolAccount.into());
+        let err = place_on(&pool, &pool_vault(Pubkey::new_unique()), 0).unwrap_err();
+        assert_eq!(err, LzComposeError::MissingPoolVault.into());
+        assert_eq!(place_on(&pool, &missing(), 0).unwrap_err(), LzComposeError::MissingPoolVault.into());
+        // bet_pool locks at 1
+        assert_eq!(place_on(&pool, &vault, 1).unwrap_err(), PlaceBetError::BettingClosed.into());
+        place_on(&pool, &vault, 0).unwrap();
+    }
+
+    #[test]
+    fn stakes_over_the_peers_rate_limits_are_refunded() {
+        let mut bet = empty_bet();
+        let mut capped = PeerConfig { stake_limit: RateLimit::new(400, 60 * 60, 0), ..peer(ADAPTER) };
+        let err = place(&mut capped, &mut bet, &place_bet(BETTOR, 0, 0, 1000), 500).unwrap_err();
+        assert_eq!(err, BetlifyError::StakeRateLimited.into());
+        assert_eq!(bet.pool, Pubkey::default());
+        place(&mut capped, &mut bet, &place_bet(BETTOR, 0, 0, 1000), 400).unwrap();
+        assert_eq!(capped.stake_limit.available, 0);
+
+        let mut limited = PeerConfig { message_limit: RateLimit::new(1, 60 * 60, 0), ..peer(ADAPTER) };
+        place(&mut limited, &mut empty_bet(), &place_bet(BETTOR, 0, 0, 1000), 500).unwrap();
+        let err = place(&mut limited, &mut empty_bet(), &place_bet(BETTOR, 0, 0, 1000), 500).unwrap_err();
+        assert_eq!(err, BetlifyError::MessageRateLimited.into());
+    }
+
+    #[test]
+    fn the_pool_account_is_the_pda_of_the_pool_bet_on() {
+        let pool = |pool_id: u64| pda::bet_pool(&store_key(), pool_id).address(&crate::ID).0;
+        assert_eq!(composed_pool(&crate::ID, store_key(), &compose(&place_bet(BETTOR, 42, 0, 1000), 500)), pool(42));
+
+        let claim = claim(BETTOR, 42);
+        assert_eq!(composed_pool(&crate::ID, store_key(), &compose(&claim, 500)), pool(0));
+    }
+
+    #[test]
+    fn refunds_go_to_the_bettor_or_else_the_adapter() {
+        assert_eq!(refund_recipient(&compose(&place_bet(BETTOR, 0, 0, 1000), 500)), msg_codec::remote_user(BETTOR));
+
+        let mut garbage = compose(&place_bet(BETTOR, 0, 0, 1000), 500);
+        garbage.compose_msg = vec![0xff; 3];
+        assert_eq!(refund_recipient(&garbage), ADAPTER);
+    }
+}
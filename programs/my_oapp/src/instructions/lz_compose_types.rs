@@ -0,0 +1,206 @@
+use crate::*;
+use anchor_lang::solana_program::system_program;
+use anchor_spl::{associated_token::get_associated_token_address, token};
+use oapp::{
+    endpoint::ID as ENDPOINT_ID,
+    endpoint_cpi::{get_accounts_for_clear_compose, LzAccount},
+    LzComposeParams,
+};
+
+/// LzComposeTypes returns the accounts `lz_compose` needs for a compose message. The Executor finds
+/// the accounts to call it with in the PDA generated by the LZ_COMPOSE_TYPES_SEED.
+#[derive(Accounts)]
+pub struct LzComposeTypes<'info> {
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    #[account(seeds = [RESPONSE_ACCOUNTS_SEED, &store.key().to_bytes()], bump = response_accounts.bump)]
+    pub response_accounts: Account<'info, ResponseAccounts>,
+}
+
+impl LzComposeTypes<'_> {
+    pub fn apply(ctx: &Context<LzComposeTypes>, params: &LzComposeParams) -> Result<Vec<LzAccount>> {
+        lz_compose_accounts(
+            ctx.program_id,
+            ctx.accounts.store.key(),
+            &ctx.accounts.store,
+            &ctx.accounts.response_accounts,
+            params,
+        )
+    }
+}
+
+// The accounts lz_compose takes for a compose message: its fixed accounts, the accounts for
+// Endpoint::clear_compose and the OFT send accounts a stake that can't be bet is refunded with.
+pub fn lz_compose_accounts(
+    program_id: &Pubkey,
+    store: Pubkey,
+    store_state: &Store,
+    response_accounts: &ResponseAccounts,
+    params: &LzComposeParams,
+) -> Result<Vec<LzAccount>> {
+    let compose = oft::decode_compose_msg(&params.message).map_err(|err| {
+        msg!("Compose decode error: {:?}", err);
+        LzComposeError::InvalidComposeMsg
+    })?;
+    let (peer, _) = pda::peer(&store, compose.src_eid).address(program_id);
+    let bet_pool = composed_pool(program_id, store, &compose);
+    let user = msg_codec::message_user(&compose.compose_msg);
+    let (bet, _) = pda::remote_bet(compose.src_eid, &user, &bet_pool).address(program_id);
+    let (pool_vault, _) = pda::pool_vault(&bet_pool).address(program_id);
+    // The OFT credits the Store's associated token account
+    let stake_vault = get_associated_token_address(&store, &store_state.stake_mint);
+
+    let mut accounts = vec![
+        // 0. payer (placeholder, Executor fills this)
+        LzAccount { pubkey: Pubkey::default(), is_signer: true, is_writable: true },
+        // 1. store PDA
+        LzAccount { pubkey: store, is_signer: false, is_writable: false },
+        // 2. peer PDA of the chain the stake came from
+        LzAccount { pubkey: peer, is_signer: false, is_writable: false },
+        // 3. bet_pool
+        LzAccount { pubkey: bet_pool, is_signer: false, is_writable: true },
+        // 4. bet (initialized if needed)
+        LzAccount { pubkey: bet, is_signer: false, is_writable: true },
+        // 5. stake_vault
+        LzAccount { pubkey: stake_vault, is_signer: false, is_writable: true },
+        // 6. pool_vault
+        LzAccount { pubkey: pool_vault, is_signer: false, is_writable: true },
+        // 7. token_program
+        LzAccount { pubkey: token::ID, is_signer: false, is_writable: false },
+        // 8. system_program
+        LzAccount { pubkey: system_program::ID, is_signer: false, is_writable: false },
+    ];
+
+    // Append accounts for Endpoint::clear_compose
+    accounts.extend(get_accounts_for_clear_compose(
+        ENDPOINT_ID,
+        &params.from,
+        &store,
+        &params.guid,
+        params.index,
+        &params.message,
+    ));
+
+    // Append the OFT send accounts registered for the source chain, with the stake vault as the
+    // token source the refund is taken from
+    if let Some(route) = response_accounts.route(compose.src_eid) {
+        accounts.extend(route.oft_send.iter().enumerate().map(|(i, account)| match i {
+            2 => LzAccount { pubkey: stake_vault, ..LzAccount::from(account) },
+            _ => LzAccount::from(account),
+        }));
+    }
+
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, claim, encoded, evm_user, place_bet, SRC_EID};
+
+    const ADAPTER: [u8; 32] = [7; 32];
+    const OFT_STORE: Pubkey = Pubkey::new_from_array([3; 32]);
+    const STAKE_MINT: Pubkey = Pubkey::new_from_array([4; 32]);
+
+    // (pubkey, is_signer, is_writable) of each account, to compare lists
+    type Listed = Vec<(Pubkey, bool, bool)>;
+
+    fn list(accounts: &[LzAccount]) -> Listed {
+        accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect()
+    }
+
+    fn params(payload: &[u8]) -> LzComposeParams {
+        let mut message = 1u64.to_be_bytes().to_vec();
+        message.extend_from_slice(&SRC_EID.to_be_bytes());
+        message.extend_from_slice(&1000u64.to_be_bytes());
+        message.extend_from_slice(&ADAPTER);
+        message.extend_from_slice(payload);
+        let to = test_utils::store_key();
+        LzComposeParams { from: OFT_STORE, to, guid: [9; 32], index: 0, message, extra_data: vec![] }
+    }
+
+    fn bet_payload(pool_id: u64) -> Vec<u8> {
+        encoded(&place_bet(evm_user(1), pool_id, 1, 1000))
+    }
+
+    fn registry() -> ResponseAccounts {
+        let account = |byte: u8, is_writable| ResponseAccount {
+            pubkey: Pubkey::new_from_array([byte; 32]),
+            is_signer: false,
+            is_writable,
+        };
+        ResponseAccounts {
+            bump: 255,
+            routes: vec![ResponseRoute {
+                eid: SRC_EID,
+                send: vec![account(10, true)],
+                oft_send: vec![account(11, false), account(12, false), account(13, true), account(14, false)],
+            }],
+        }
+    }
+
+    // The accounts for `payload`, split into the fixed accounts, the clear_compose accounts and the
+    // refund accounts
+    fn accounts_of(payload: &[u8], registry: &ResponseAccounts) -> (Listed, Listed, Listed) {
+        let store = test_utils::store_key();
+        let store_state = Store { stake_mint: STAKE_MINT, oft_store: OFT_STORE, ..test_utils::store() };
+        let params = params(payload);
+        let accounts = lz_compose_accounts(&crate::ID, store, &store_state, registry, &params).unwrap();
+        let clear =
+            get_accounts_for_clear_compose(ENDPOINT_ID, &OFT_STORE, &store, &params.guid, 0, &params.message);
+        let (fixed, rest) = accounts.split_at(9);
+        let (clear_accounts, refund) = rest.split_at(clear.len());
+        assert_eq!(list(clear_accounts), list(&clear));
+        (list(fixed), list(clear_accounts), list(refund))
+    }
+
+    #[test]
+    fn compose_accounts_are_those_of_the_bet() {
+        let store = test_utils::store_key();
+        let address = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        let pool = address(&[BET_POOL_SEED, store.as_ref(), &42u64.to_le_bytes()]);
+        let user = msg_codec::remote_user(evm_user(1));
+        let expected = [
+            (Pubkey::default(), true, true),
+            (store, false, false),
+            (address(&[PEER_SEED, store.as_ref(), &SRC_EID.to_be_bytes()]), false, false),
+            (pool, false, true),
+            (address(&[BET_SEED, &SRC_EID.to_be_bytes(), &user, pool.as_ref()]), false, true),
+            (get_associated_token_address(&store, &STAKE_MINT), false, true),
+            (address(&[POOL_VAULT_SEED, pool.as_ref()]), false, true),
+            (token::ID, false, false),
+            (system_program::ID, false, false),
+        ];
+        assert_eq!(accounts_of(&bet_payload(42), &registry()).0, expected);
+    }
+
+    #[test]
+    fn refunds_take_the_stake_from_the_stake_vault() {
+        let route = registry().routes.remove(0);
+        let mut expected: Listed = route.oft_send.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        expected[2].0 = get_associated_token_address(&test_utils::store_key(), &STAKE_MINT);
+        assert_eq!(accounts_of(&bet_payload(42), &registry()).2, expected);
+
+        let unregistered = ResponseAccounts { bump: 255, routes: vec![] };
+        assert!(accounts_of(&bet_payload(42), &unregistered).2.is_empty());
+    }
+
+    #[test]
+    fn payloads_that_are_not_bets_still_get_accounts_to_be_refunded_with() {
+        for payload in [encoded(&claim(evm_user(1), 42)), vec![0xff; 3]] {
+            let (fixed, _, refund) = accounts_of(&payload, &registry());
+            assert_eq!(fixed[3], accounts_of(&bet_payload(0), &registry()).0[3]);
+            assert_eq!(refund.len(), 4);
+        }
+    }
+
+    #[test]
+    fn malformed_compose_messages_are_rejected() {
+        let store_state = test_utils::store();
+        let mut params = params(&bet_payload(42));
+        params.message.truncate(oft::COMPOSE_MSG_OFFSET - 1);
+        let err = lz_compose_accounts(&crate::ID, test_utils::store_key(), &store_state, &registry(), &params)
+            .unwrap_err();
+        assert_eq!(err, LzComposeError::InvalidComposeMsg.into());
+    }
+}
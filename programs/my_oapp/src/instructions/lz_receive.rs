@@ -3,15 +3,20 @@ use anchor_lang::prelude::*;
 use oapp::{
     endpoint::{
         cpi::accounts::Clear,
-        instructions::ClearParams,
+        instructions::{ClearParams, SendParams},
         ConstructCPIContext, ID as ENDPOINT_ID,
     },
     LzReceiveParams,
 };
-use anchor_lang::solana_program::system_program;
+use anchor_lang::{system_program, Discriminator};
 
 use crate::state::{bet_pool::BetPool, bet::Bet, PoolStatus};
-use crate::{PlaceBetError};
+use crate::errors::MyOAppError;
+
+// The accounts each message acts on (pools, bets, identity links) follow the fixed accounts in
+// `remaining_accounts`, in the order `required_accounts` lists them and LzReceiveTypes returns them.
+// The accounts for Endpoint::clear come next, then the Endpoint::send accounts registered for the
+// source chain in ResponseAccounts and, for claims paid out in tokens, the OFT send accounts.
 
 #[derive(Accounts)]
 #[instruction(params: LzReceiveParams)]
@@ -20,6 +25,7 @@ pub struct LzReceive<'info> {
     pub payer: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [PEER_SEED, &store.key().to_bytes(), &params.src_eid.to_be_bytes()],
         bump = peer.bump,
         constraint = params.sender == peer.peer_address
@@ -29,27 +35,64 @@ pub struct LzReceive<'info> {
     #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
     pub store: Account<'info, Store>,
 
-    #[account(init_if_needed, payer = payer, seeds = [b"betpool", store.key().as_ref(), &store.pools_count.to_le_bytes()], bump, space = 8 + BetPool::INIT_SPACE)]
-    pub bet_pool: Account<'info, BetPool>,
+    #[account(
+        mut,
+        seeds = [NONCE_SEED, &store.key().to_bytes(), &params.src_eid.to_be_bytes(), &params.sender],
+        bump = nonce_account.bump
+    )]
+    pub nonce_account: Account<'info, Nonce>,
 
-    #[account(init_if_needed, payer = payer, seeds = [b"bet", params.sender.as_ref(), bet_pool.key().as_ref()], bump, space = 8 + Bet::INIT_SPACE)]
-    pub bet: Account<'info, Bet>,
+    /// CHECK: Only created, by lz_receive itself, when a non-blocking peer's message fails
+    #[account(
+        mut,
+        seeds = [FAILED_MESSAGE_SEED, &store.key().to_bytes(), &params.guid],
+        bump
+    )]
+    pub failed_message: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [FEE_VAULT_SEED, &store.key().to_bytes()], bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    #[account(seeds = [RESPONSE_ACCOUNTS_SEED, &store.key().to_bytes()], bump = response_accounts.bump)]
+    pub response_accounts: Account<'info, ResponseAccounts>,
 
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> LzReceive<'info> {
-    pub fn apply(ctx: &mut Context<Self>, params: &LzReceiveParams) -> Result<()> {
-        let seeds: &[&[u8]] = &[STORE_SEED, &[ctx.accounts.store.bump]];
-        let clear_accounts = &ctx.remaining_accounts[0..Clear::MIN_ACCOUNTS_LEN];
+    pub fn apply(ctx: &mut Context<'_, '_, 'info, 'info, Self>, params: &LzReceiveParams) -> Result<()> {
+        // A message that doesn't decode acts on no accounts. It is still cleared, so a non-blocking
+        // peer keeps it as failed like any other message that can't be executed.
+        let (envelope, mut rejected): (_, Result<()>) = match msg_codec::decode_betlify_envelope(&params.message) {
+            Ok(envelope) => (Some(envelope), Ok(())),
+            Err(err) => {
+                msg!("Decode error: {}", err);
+                (None, Err(err.into()))
+            }
+        };
+        let actions = envelope.as_ref().map_or(&[][..], |envelope| envelope.message.actions());
+        let request_id = envelope.as_ref().map_or(0, |envelope| envelope.header.request_id);
+
+        let store_key = ctx.accounts.store.key();
+        let first_pool_id = ctx.accounts.store.pools_count;
+        let required = required_accounts(actions, first_pool_id);
+        verify_message_accounts(ctx.program_id, store_key, params.src_eid, &required, ctx.remaining_accounts)?;
+        let (message_infos, endpoint_accounts) = ctx.remaining_accounts.split_at(required.len());
+        require!(endpoint_accounts.len() >= Clear::MIN_ACCOUNTS_LEN, BetlifyError::MissingClearAccounts);
+        let (clear_accounts, response_accounts) = endpoint_accounts.split_at(Clear::MIN_ACCOUNTS_LEN);
+        let send_len = ctx.accounts.response_accounts.send_len(params.src_eid).min(response_accounts.len());
+        let (send_accounts, oft_send_accounts) = response_accounts.split_at(send_len);
+        let payout_accounts =
+            if ctx.accounts.store.oft_program == Pubkey::default() { send_accounts } else { oft_send_accounts };
 
+        let seeds: &[&[u8]] = &[STORE_SEED, &[ctx.accounts.store.bump]];
         oapp::endpoint_cpi::clear(
             ENDPOINT_ID,
-            ctx.accounts.store.key(),
+            store_key,
             clear_accounts,
             seeds,
             ClearParams {
-                receiver: ctx.accounts.store.key(),
+                receiver: store_key,
                 src_eid: params.src_eid,
                 sender: params.sender,
                 nonce: params.nonce,
@@ -58,75 +101,87 @@ impl<'info> LzReceive<'info> {
             },
         )?;
 
+        ctx.accounts.nonce_account.accept(params.nonce, ctx.accounts.peer.ordered_nonce)?;
+
+        // Paused messages revert so they can be retried once the pause is lifted
+        check_paused(&ctx.accounts.store, &ctx.accounts.peer, actions)?;
+        if rejected.is_ok() {
+            rejected = check_msg_types(&ctx.accounts.peer, params.src_eid, actions);
+        }
 
-        match msg_codec::decode_betlify_message(&params.message) {
-            Ok(betlify_msg) => {
-                match betlify_msg {
-                    msg_codec::BetlifyMessage::CreatePool { question, options, start_time, lock_time, end_time, pool_id } => {
-                        msg!("Creating pool {}", pool_id);
-
-                        let bet_pool = &mut ctx.accounts.bet_pool;
-                        let store = &mut ctx.accounts.store;
-
-                        bet_pool.id = pool_id;
-                        bet_pool.creator = ctx.accounts.payer.key();
-                        bet_pool.question = question;
-                        bet_pool.status = PoolStatus::Open;
-                        bet_pool.winning_option = 0; // Default to first option
-                        bet_pool.start_time = start_time;
-                        bet_pool.lock_time = lock_time;
-                        bet_pool.end_time = end_time;
-                        bet_pool.unique_bettors = 0;
-                        bet_pool.pool_amount = 0;
-                        bet_pool.option_amounts = vec![0; options.len()];
-                        bet_pool.is_result_set = false;
-                        bet_pool.result = 0; // Default to first option
-
-                        store.pools_count = store.pools_count.checked_add(1).unwrap();
-                    }
-
-                    msg_codec::BetlifyMessage::PlaceBet { pool_id, option, amount } => {
-                        msg!("Placing bet on pool {}", pool_id);
-
-                        let bet = &mut ctx.accounts.bet; 
-
-                        let pool = &mut ctx.accounts.bet_pool;
-                        let clock = Clock::get()?;
-
-                        // Enforce time window
-                        require!(clock.unix_timestamp >= pool.start_time && clock.unix_timestamp < pool.lock_time, PlaceBetError::BettingClosed);
-                        // Enforce valid option (check against option_amounts length)
-                        require!((option as usize) < pool.option_amounts.len(), PlaceBetError::InvalidOption);
-                        // Prevent duplicate bets (if bet already exists, Anchor will error on init)
-                        // Update pool amounts
-                        pool.pool_amount = pool.pool_amount.checked_add(amount).unwrap();
-                        pool.option_amounts[option as usize] = pool.option_amounts[option as usize].checked_add(amount).unwrap();
-                        // Update unique bettors
-                        pool.unique_bettors = pool.unique_bettors.checked_add(1).unwrap();
-                        // Set bet fields
-                        bet.user = params.sender;
-                        bet.pool = ctx.accounts.bet_pool.key();
-                        bet.option = option;
-                        bet.amount = amount;
-                        bet.claimed = false;
-                    }
-
-                    msg_codec::BetlifyMessage::ResolveMarket { pool_id, .. } => {
-                        msg!("Resolving pool {}", pool_id);
-
-                        // TODO: Implement resolve market logic
-                    }
-
-                    msg_codec::BetlifyMessage::ClaimWinnings { pool_id, .. } => {
-                        msg!("Claiming winnings for pool {}", pool_id);
-                        // TODO: Implement claim winnings logic using OFT for cross-chain payouts
-                    }
+        // Rate limits count every action in a batch, and the stake of every PlaceBet in it
+        let stake = actions
+            .iter()
+            .try_fold(0u64, |total, message| match message {
+                msg_codec::BetlifyMessage::PlaceBet { amount, .. } => total.checked_add(*amount),
+                _ => Some(total),
+            })
+            .ok_or(BetlifyError::StakeRateLimited)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.peer.consume_inbound(actions.len() as u64, stake, now)?;
+
+        // A non-blocking peer keeps failed messages instead of reverting, so the store is
+        // snapshotted to undo a partially applied batch. Message accounts are only written on success.
+        let store_snapshot = ctx.accounts.peer.non_blocking.then(|| (*ctx.accounts.store).clone());
+
+        let result = rejected
+            .and_then(|()| {
+                load_message_accounts(
+                    ctx.program_id,
+                    store_key,
+                    params.src_eid,
+                    &required,
+                    message_infos,
+                    &ctx.accounts.payer,
+                    &ctx.accounts.system_program,
+                )
+            })
+            .and_then(|mut accounts| {
+                let payouts = execute_actions(&mut ctx.accounts.store, &mut accounts, params.src_eid, actions)?;
+                accounts.exit(ctx.program_id)?;
+                Ok(payouts)
+            });
+
+        match result {
+            Ok(payouts) => {
+                let ack = acknowledges(&ctx.accounts.peer, &ctx.accounts.store, &payouts);
+                for payout in payouts {
+                    issue_payout(
+                        ctx.program_id,
+                        &ctx.accounts.store,
+                        &ctx.accounts.peer,
+                        payout_accounts,
+                        params.src_eid,
+                        request_id,
+                        payout,
+                    )?;
+                }
+                if ack {
+                    let (pool_id, bet) = ack_target(ctx.program_id, store_key, params.src_eid, first_pool_id, actions);
+                    let accounts = ack_accounts(ctx, send_accounts);
+                    send_ack(&accounts, params.src_eid, params.guid, request_id, msg_codec::ACK_EXECUTED, pool_id, bet)?;
                 }
             }
-
             Err(err) => {
-                msg!("Decode error: {:?}", err);
-                return Err(BetlifyError::InvalidMessage.into());
+                let Some(store) = store_snapshot else {
+                    return Err(err);
+                };
+                ctx.accounts.store.set_inner(store);
+
+                let error_code = error_code_of(&err);
+                msg!("Message {:?} from eid {} failed: {}", params.guid, params.src_eid, err);
+                store_failed_message(ctx, params, error_code)?;
+
+                if ctx.accounts.peer.notify_failures {
+                    // Lets the source chain adapter refund or surface the failure to the user
+                    let notice = msg_codec::BetlifyMessage::MessageFailed { guid: params.guid, error_code };
+                    let message = msg_codec::encode_betlify_message(request_id, now as u64, &notice);
+                    send_to_source(&ctx.accounts.store, &ctx.accounts.peer, send_accounts, params.src_eid, message)?;
+                }
+                if acknowledges(&ctx.accounts.peer, &ctx.accounts.store, &[]) {
+                    let accounts = ack_accounts(ctx, send_accounts);
+                    send_ack(&accounts, params.src_eid, params.guid, request_id, msg_codec::ACK_FAILED, 0, [0; 32])?;
+                }
             }
         }
 
@@ -134,7 +189,766 @@ impl<'info> LzReceive<'info> {
     }
 }
 
-#[error_code]
+// Winnings owed to a bettor on the source chain, issued once every action of a message succeeded
+#[derive(Debug, PartialEq)]
+pub struct Payout {
+    pub pool_id: u64,
+    pub pool: Pubkey,
+    pub recipient: [u8; 32],
+    pub amount: u64,
+}
+
+// Pause and message type checks shared by lz_receive and retry_failed_message
+pub fn check_inbound(
+    store: &Store,
+    peer: &PeerConfig,
+    src_eid: u32,
+    actions: &[msg_codec::BetlifyMessage],
+) -> Result<()> {
+    check_paused(store, peer, actions)?;
+    check_msg_types(peer, src_eid, actions)
+}
+
+pub fn check_paused(store: &Store, peer: &PeerConfig, actions: &[msg_codec::BetlifyMessage]) -> Result<()> {
+    require!(!peer.paused, MyOAppError::PeerPaused);
+    if store.paused {
+        let only_claims = actions
+            .iter()
+            .all(|message| matches!(message, msg_codec::BetlifyMessage::ClaimWinnings { .. }));
+        require!(only_claims && store.claims_open(), MyOAppError::Paused);
+    }
+    Ok(())
+}
+
+// Unlike a pause, a message type the peer may not send won't pass later, so lz_receive treats it
+// like a failed action
+pub fn check_msg_types(peer: &PeerConfig, src_eid: u32, actions: &[msg_codec::BetlifyMessage]) -> Result<()> {
+    for message in actions {
+        if !peer.allows(message.msg_type()) {
+            msg!("Rejected message type {} from eid {}", message.msg_type(), src_eid);
+            return Err(BetlifyError::MessageTypeNotAllowed.into());
+        }
+    }
+    Ok(())
+}
+
+// An account a received message acts on, identified by what its PDA is derived from
+#[derive(Clone, Copy, PartialEq)]
+pub enum MessageAccount {
+    Pool { pool_id: u64 },
+    Bet { pool_id: u64, user: [u8; 32] },
+    IdentityLink { user: [u8; 32] },
+    CreatorPoolIndex { creator: [u8; 32], creator_pool_id: u64 },
+}
+
+impl MessageAccount {
+    pub fn seeds(&self, program_id: &Pubkey, store: Pubkey, src_eid: u32) -> pda::PdaSeeds {
+        match *self {
+            MessageAccount::Pool { pool_id } => pda::bet_pool(&store, pool_id),
+            MessageAccount::Bet { pool_id, user } => {
+                let (pool, _) = pda::bet_pool(&store, pool_id).address(program_id);
+                pda::remote_bet(src_eid, &user, &pool)
+            }
+            MessageAccount::IdentityLink { user } => pda::identity_link(&store, src_eid, &user),
+            MessageAccount::CreatorPoolIndex { creator, creator_pool_id } => {
+                pda::creator_pool_index(&store, src_eid, &creator, creator_pool_id)
+            }
+        }
+    }
+
+    pub fn address(&self, program_id: &Pubkey, store: Pubkey, src_eid: u32) -> (Pubkey, u8) {
+        self.seeds(program_id, store, src_eid).address(program_id)
+    }
+
+    pub fn space(&self) -> usize {
+        match self {
+            MessageAccount::Pool { .. } => 8 + BetPool::INIT_SPACE,
+            MessageAccount::Bet { .. } => 8 + Bet::INIT_SPACE,
+            MessageAccount::IdentityLink { .. } => 8 + IdentityLink::INIT_SPACE,
+            MessageAccount::CreatorPoolIndex { .. } => 8 + CreatorPoolIndex::INIT_SPACE,
+        }
+    }
+
+    pub fn discriminator(&self) -> [u8; 8] {
+        match self {
+            MessageAccount::Pool { .. } => BetPool::discriminator(),
+            MessageAccount::Bet { .. } => Bet::discriminator(),
+            MessageAccount::IdentityLink { .. } => IdentityLink::discriminator(),
+            MessageAccount::CreatorPoolIndex { .. } => CreatorPoolIndex::discriminator(),
+        }
+    }
+
+    fn missing(&self) -> BetlifyError {
+        match self {
+            MessageAccount::Pool { .. } => BetlifyError::MissingPoolAccount,
+            MessageAccount::Bet { .. } => BetlifyError::MissingBetAccount,
+            MessageAccount::IdentityLink { .. } => BetlifyError::MissingIdentityLinkAccount,
+            MessageAccount::CreatorPoolIndex { .. } => BetlifyError::MissingCreatorPoolIndexAccount,
+        }
+    }
+
+    fn invalid(&self) -> BetlifyError {
+        match self {
+            MessageAccount::Pool { .. } => BetlifyError::InvalidPoolAccount,
+            MessageAccount::Bet { .. } => BetlifyError::InvalidBetAccount,
+            MessageAccount::IdentityLink { .. } => BetlifyError::InvalidIdentityLinkAccount,
+            MessageAccount::CreatorPoolIndex { .. } => BetlifyError::InvalidCreatorPoolIndexAccount,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct RequiredAccount {
+    pub account: MessageAccount,
+    pub writable: bool,
+    pub init: bool, // Created by the message if it doesn't exist yet
+}
+
+// The accounts the actions of a message need, each once, in the order they are first needed.
+// Pools the message creates get the global ids from `first_pool_id` (the Store's pool count) on.
+pub fn required_accounts(
+    actions: &[msg_codec::BetlifyMessage],
+    first_pool_id: u64,
+) -> Vec<RequiredAccount> {
+    let mut next_pool_id = first_pool_id;
+    let mut required: Vec<RequiredAccount> = Vec::new();
+    let mut add = |account, writable, init| {
+        match required.iter_mut().find(|existing| existing.account == account) {
+            Some(existing) => {
+                existing.writable |= writable;
+                existing.init |= init;
+            }
+            None => required.push(RequiredAccount { account, writable, init }),
+        }
+    };
+    for action in actions {
+        match *action {
+            msg_codec::BetlifyMessage::CreatePool { creator, creator_pool_id, .. } => {
+                let creator = msg_codec::remote_user(creator);
+                add(MessageAccount::Pool { pool_id: next_pool_id }, true, true);
+                add(MessageAccount::CreatorPoolIndex { creator, creator_pool_id }, true, true);
+                next_pool_id = next_pool_id.wrapping_add(1); // Past u64::MAX the CreatePool fails
+            }
+            msg_codec::BetlifyMessage::PlaceBet { authority, pool_id, .. } => {
+                let user = msg_codec::remote_user(authority);
+                add(MessageAccount::Pool { pool_id }, true, false);
+                add(MessageAccount::Bet { pool_id, user }, true, true);
+            }
+            msg_codec::BetlifyMessage::ResolveMarket { pool_id, .. } => {
+                add(MessageAccount::Pool { pool_id }, true, false);
+            }
+            msg_codec::BetlifyMessage::ClaimWinnings { authority, pool_id } => {
+                let user = msg_codec::remote_user(authority);
+                add(MessageAccount::Pool { pool_id }, false, false);
+                add(MessageAccount::Bet { pool_id, user }, true, false);
+            }
+            msg_codec::BetlifyMessage::LinkWallet { authority, .. } => {
+                let user = msg_codec::remote_user(authority);
+                add(MessageAccount::IdentityLink { user }, true, true);
+            }
+            // Rejected when executed
+            msg_codec::BetlifyMessage::PayoutIssued { .. }
+            | msg_codec::BetlifyMessage::Batch(_)
+            | msg_codec::BetlifyMessage::MessageFailed { .. }
+            | msg_codec::BetlifyMessage::ActionAck { .. } => {}
+        }
+    }
+    required
+}
+
+// Checks that `infos` start with exactly the accounts in `required`. A mismatch is the caller's
+// mistake rather than the message's, so it always reverts.
+pub fn verify_message_accounts(
+    program_id: &Pubkey,
+    store: Pubkey,
+    src_eid: u32,
+    required: &[RequiredAccount],
+    infos: &[AccountInfo],
+) -> Result<()> {
+    for (index, required) in required.iter().enumerate() {
+        let info = infos.get(index).ok_or(required.account.missing())?;
+        let (address, _) = required.account.address(program_id, store, src_eid);
+        require_keys_eq!(info.key(), address, required.account.invalid());
+        require!(info.is_writable || !required.writable, required.account.invalid());
+    }
+    Ok(())
+}
+
+// The pools, bets, identity links and creator pool indexes of a message, loaded from its verified
+// accounts
+#[derive(Default)]
+pub struct MessageAccounts<'info> {
+    pools: Vec<(MessageAccount, Account<'info, BetPool>)>,
+    bets: Vec<(MessageAccount, Account<'info, Bet>)>,
+    identity_links: Vec<(MessageAccount, Account<'info, IdentityLink>)>,
+    creator_pool_indexes: Vec<(MessageAccount, Account<'info, CreatorPoolIndex>)>,
+}
+
+impl<'info> MessageAccounts<'info> {
+    fn pool(&mut self, pool_id: u64) -> Result<&mut Account<'info, BetPool>> {
+        self.pools
+            .iter_mut()
+            .find(|(account, _)| *account == MessageAccount::Pool { pool_id })
+            .map(|(_, pool)| pool)
+            .ok_or_else(|| BetlifyError::MissingPoolAccount.into())
+    }
+
+    fn pool_and_bet(
+        &mut self,
+        pool_id: u64,
+        user: [u8; 32],
+    ) -> Result<(&mut Account<'info, BetPool>, &mut Account<'info, Bet>)> {
+        let pool = self
+            .pools
+            .iter_mut()
+            .find(|(account, _)| *account == MessageAccount::Pool { pool_id })
+            .map(|(_, pool)| pool)
+            .ok_or(BetlifyError::MissingPoolAccount)?;
+        let bet = self
+            .bets
+            .iter_mut()
+            .find(|(account, _)| *account == MessageAccount::Bet { pool_id, user })
+            .map(|(_, bet)| bet)
+            .ok_or(BetlifyError::MissingBetAccount)?;
+        Ok((pool, bet))
+    }
+
+    fn identity_link(&mut self, user: [u8; 32]) -> Result<&mut Account<'info, IdentityLink>> {
+        self.identity_links
+            .iter_mut()
+            .find(|(account, _)| *account == MessageAccount::IdentityLink { user })
+            .map(|(_, link)| link)
+            .ok_or_else(|| BetlifyError::MissingIdentityLinkAccount.into())
+    }
+
+    fn creator_pool_index(
+        &mut self,
+        creator: [u8; 32],
+        creator_pool_id: u64,
+    ) -> Result<&mut Account<'info, CreatorPoolIndex>> {
+        self.creator_pool_indexes
+            .iter_mut()
+            .find(|(account, _)| *account == MessageAccount::CreatorPoolIndex { creator, creator_pool_id })
+            .map(|(_, index)| index)
+            .ok_or_else(|| BetlifyError::MissingCreatorPoolIndexAccount.into())
+    }
+
+    // Persists the writable accounts, which only happens once every action succeeded
+    pub fn exit(&self, program_id: &Pubkey) -> Result<()> {
+        for (_, pool) in &self.pools {
+            if pool.to_account_info().is_writable {
+                pool.exit(program_id)?;
+            }
+        }
+        for (_, bet) in &self.bets {
+            bet.exit(program_id)?;
+        }
+        for (_, link) in &self.identity_links {
+            link.exit(program_id)?;
+        }
+        for (_, index) in &self.creator_pool_indexes {
+            index.exit(program_id)?;
+        }
+        Ok(())
+    }
+}
+
+// Loads the accounts verified by `verify_message_accounts`, creating the ones a message
+// initializes. Accounts that must already exist and don't are the message's failure.
+pub fn load_message_accounts<'info>(
+    program_id: &Pubkey,
+    store: Pubkey,
+    src_eid: u32,
+    required: &[RequiredAccount],
+    infos: &'info [AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<MessageAccounts<'info>> {
+    let mut accounts = MessageAccounts::default();
+    for (required, info) in required.iter().zip(infos) {
+        let created = info.owner == &system_program::ID;
+        if created {
+            require!(required.init, required.account.missing());
+            let seeds = required.account.seeds(program_id, store, src_eid);
+            let bump = [seeds.address(program_id).1];
+            let signer_seeds = seeds.signer(&bump);
+            create_pda(program_id, payer, info, system_program, &signer_seeds, required.account.space())?;
+            info.try_borrow_mut_data()?[..8].copy_from_slice(&required.account.discriminator());
+        }
+
+        // Accounts a message may create get their bump on every load. Like the rest of the account it is
+        // only written by `exit` once the message succeeds, so an account created by a message that
+        // failed keeps a zero bump until a later message, or its retry, succeeds with it.
+        match required.account {
+            MessageAccount::Pool { .. } => {
+                let mut pool = Account::<BetPool>::try_from(info)?;
+                if required.init {
+                    pool.bump = required.account.address(program_id, store, src_eid).1;
+                }
+                accounts.pools.push((required.account, pool));
+            }
+            MessageAccount::Bet { .. } => {
+                let mut bet = Account::<Bet>::try_from(info)?;
+                if required.init {
+                    bet.bump = required.account.address(program_id, store, src_eid).1;
+                }
+                accounts.bets.push((required.account, bet));
+            }
+            MessageAccount::IdentityLink { .. } => {
+                let mut link = Account::<IdentityLink>::try_from(info)?;
+                if required.init {
+                    link.bump = required.account.address(program_id, store, src_eid).1;
+                }
+                accounts.identity_links.push((required.account, link));
+            }
+            MessageAccount::CreatorPoolIndex { .. } => {
+                let mut index = Account::<CreatorPoolIndex>::try_from(info)?;
+                if required.init {
+                    index.bump = required.account.address(program_id, store, src_eid).1;
+                }
+                accounts.creator_pool_indexes.push((required.account, index));
+            }
+        }
+    }
+    Ok(accounts)
+}
+
+// Creates a program owned PDA of `space` bytes paid by `payer`. Like Anchor's `init`, this also
+// works when someone already sent lamports to the address.
+fn create_pda<'info>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount { from: payer.clone(), to: account.clone() },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            program_id,
+        );
+    }
+
+    let top_up = rent.saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer.clone(), to: account.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign { account_to_assign: account.clone() },
+            &[signer_seeds],
+        ),
+        program_id,
+    )
+}
+
+// Runs the actions of a message in order. Payouts are returned rather than sent, so nothing leaves
+// the program until the whole message has succeeded.
+pub fn execute_actions(
+    store: &mut Account<Store>,
+    accounts: &mut MessageAccounts,
+    src_eid: u32,
+    actions: &[msg_codec::BetlifyMessage],
+) -> Result<Vec<Payout>> {
+    let mut payouts = Vec::new();
+    for message in actions {
+        if let Some(payout) = execute(store, accounts, src_eid, message.clone())? {
+            payouts.push(payout);
+        }
+    }
+    Ok(payouts)
+}
+
+fn execute(
+    store: &mut Account<Store>,
+    accounts: &mut MessageAccounts,
+    src_eid: u32,
+    message: msg_codec::BetlifyMessage,
+) -> Result<Option<Payout>> {
+    match message {
+        msg_codec::BetlifyMessage::CreatePool {
+            creator,
+            question,
+            options,
+            start_time,
+            lock_time,
+            end_time,
+            creator_pool_id,
+            resolution_source,
+        } => {
+            // Cross-chain pools take the next global id, like pools created on Solana
+            let pool_id = store.pools_count;
+            msg!("Creating pool {} for creator pool {}", pool_id, creator_pool_id);
+
+            let clock = Clock::get()?;
+            validate_pool_params(
+                store,
+                &question,
+                &options,
+                start_time,
+                lock_time,
+                end_time,
+                clock.unix_timestamp,
+            )?;
+            let bet_pool = accounts.pool(pool_id)?;
+            require!(bet_pool.option_amounts.is_empty(), BetlifyError::PoolAlreadyExists);
+
+            bet_pool.id = pool_id;
+            // Nobody on Solana owns a cross-chain pool; its creator resolves it from the source chain
+            bet_pool.creator = Pubkey::default();
+            bet_pool.origin_eid = src_eid;
+            let remote_creator = msg_codec::remote_user(creator);
+            bet_pool.remote_creator = remote_creator;
+            bet_pool.question = question;
+            bet_pool.status = PoolStatus::Open;
+            bet_pool.winning_option = 0; // Default to first option
+            bet_pool.start_time = start_time;
+            bet_pool.lock_time = lock_time;
+            bet_pool.end_time = end_time;
+            bet_pool.resolution_deadline = resolution_deadline(end_time)?;
+            bet_pool.unique_bettors = 0;
+            bet_pool.pool_amount = 0;
+            bet_pool.option_amounts = vec![0; options.len()];
+            bet_pool.is_result_set = false;
+            bet_pool.result = 0; // Default to first option
+            bet_pool.resolution_source = resolution_source;
+            bet_pool.evidence_uri = String::new();
+            bet_pool.evidence_hash = [0; 32];
+
+            let index = accounts.creator_pool_index(remote_creator, creator_pool_id)?;
+            require!(index.creator == [0; 32], BetlifyError::PoolAlreadyExists);
+            index.origin_eid = src_eid;
+            index.creator = remote_creator;
+            index.creator_pool_id = creator_pool_id;
+            index.pool_id = pool_id;
+
+            store.pools_count = store.pools_count.checked_add(1).ok_or(CreatePoolError::TooManyPools)?;
+        }
+
+        msg_codec::BetlifyMessage::PlaceBet { authority, pool_id, option, amount } => {
+            msg!("Placing bet on pool {}", pool_id);
+
+            // Once stakes are settled in tokens, EVM bets must arrive funded through lz_compose
+            require!(store.stake_mint == Pubkey::default(), BetlifyError::UnfundedBet);
+
+            let user = msg_codec::remote_user(authority);
+            let (bet_pool, bet) = accounts.pool_and_bet(pool_id, user)?;
+            require!(bet_pool.id == pool_id && !bet_pool.option_amounts.is_empty(), BetlifyError::MissingPoolAccount);
+
+            let pool_key = bet_pool.key();
+            let clock = Clock::get()?;
+            record_bet(bet_pool, pool_key, bet, option, amount, clock.unix_timestamp)?;
+            bet.user = user;
+            bet.origin_eid = src_eid;
+        }
+
+        msg_codec::BetlifyMessage::ResolveMarket { resolver, pool_id, winning_option, evidence_uri, evidence_hash } => {
+            msg!("Resolving pool {}", pool_id);
+
+            let bet_pool = accounts.pool(pool_id)?;
+
+            // Only the remote creator of a cross-chain pool may resolve it from its source chain
+            require!(
+                bet_pool.origin_eid == src_eid && bet_pool.remote_creator == msg_codec::remote_user(resolver),
+                BetlifyError::UnauthorizedResolver
+            );
+
+            let clock = Clock::get()?;
+            resolve_pool(bet_pool, winning_option, &evidence_uri, evidence_hash, clock.unix_timestamp)?;
+        }
+
+        msg_codec::BetlifyMessage::ClaimWinnings { authority, pool_id } => {
+            msg!("Claiming winnings for pool {}", pool_id);
+
+            let (bet_pool, bet) = accounts.pool_and_bet(pool_id, msg_codec::remote_user(authority))?;
+            let pool_key = bet_pool.key();
+            // Claims on a voided pool get the stake back
+            let amount = if bet_pool.status == PoolStatus::Voided {
+                settle_refund(bet_pool, pool_key, bet)?
+            } else {
+                settle_claim(bet_pool, pool_key, bet)?
+            };
+            let amount = if store.oft_program == Pubkey::default() { amount } else { store.without_stake_dust(amount) };
+            require!(amount > 0, BetlifyError::NothingToClaim);
+
+            return Ok(Some(Payout { pool_id, pool: pool_key, recipient: bet.user, amount }));
+        }
+
+        msg_codec::BetlifyMessage::LinkWallet { authority, solana_pubkey } => {
+            msg!("Linking wallet for eid {}", src_eid);
+
+            let user = msg_codec::remote_user(authority);
+            let identity_link = accounts.identity_link(user)?;
+            identity_link.src_eid = src_eid;
+            identity_link.user = user;
+            identity_link.solana_pubkey = Pubkey::new_from_array(solana_pubkey);
+        }
+
+        // Payouts, failure notices and acks only flow out of this program, and batches are flattened
+        // by the caller
+        msg_codec::BetlifyMessage::PayoutIssued { .. }
+        | msg_codec::BetlifyMessage::Batch(_)
+        | msg_codec::BetlifyMessage::MessageFailed { .. }
+        | msg_codec::BetlifyMessage::ActionAck { .. } => {
+            return Err(MyOAppError::InvalidMessageType.into());
+        }
+    }
+    Ok(None)
+}
+
+// The pool and bet PDA an ack reports: those of the last action that has them
+pub fn ack_target(
+    program_id: &Pubkey,
+    store: Pubkey,
+    src_eid: u32,
+    first_pool_id: u64,
+    actions: &[msg_codec::BetlifyMessage],
+) -> (u64, [u8; 32]) {
+    let mut next_pool_id = first_pool_id;
+    let mut target = (0, [0; 32]);
+    for action in actions {
+        match *action {
+            // Tells the creator the global id of its pool
+            msg_codec::BetlifyMessage::CreatePool { .. } => {
+                target = (next_pool_id, [0; 32]);
+                next_pool_id = next_pool_id.wrapping_add(1);
+            }
+            msg_codec::BetlifyMessage::PlaceBet { authority, pool_id, .. }
+            | msg_codec::BetlifyMessage::ClaimWinnings { authority, pool_id } => {
+                let user = msg_codec::remote_user(authority);
+                let (bet, _) = MessageAccount::Bet { pool_id, user }.address(program_id, store, src_eid);
+                target = (pool_id, bet.to_bytes());
+            }
+            msg_codec::BetlifyMessage::ResolveMarket { pool_id, .. } => target = (pool_id, [0; 32]),
+            _ => {}
+        }
+    }
+    target
+}
+
+// Pays out claimed winnings on the source chain. `accounts` are the Endpoint::send accounts, or the
+// OFT send accounts when stakes are held in tokens.
+pub fn issue_payout<'info>(
+    program_id: &Pubkey,
+    store: &Account<'info, Store>,
+    peer: &PeerConfig,
+    accounts: &[AccountInfo<'info>],
+    dst_eid: u32,
+    request_id: u64,
+    payout: Payout,
+) -> Result<()> {
+    if store.oft_program != Pubkey::default() {
+        // Stakes are held in tokens, so pay the winnings out through the OFT
+        pay_out_with_oft(program_id, store, peer, accounts, dst_eid, payout)
+    } else {
+        // Tell the source chain adapter to release the winnings to the bettor
+        let message = msg_codec::BetlifyMessage::PayoutIssued {
+            pool_id: payout.pool_id,
+            recipient: payout.recipient,
+            amount: payout.amount,
+        };
+        // Echo the client's request id so the adapter can match the payout to the claim
+        let clock = Clock::get()?;
+        let message = msg_codec::encode_betlify_message(request_id, clock.unix_timestamp as u64, &message);
+        send_to_source(store, peer, accounts, dst_eid, message)
+    }
+}
+
+// Whether a received message that issued `payouts` is acknowledged to its source chain. OFT payouts
+// take the send accounts, and the tokens arriving already tell the user.
+pub fn acknowledges(peer: &PeerConfig, store: &Store, payouts: &[Payout]) -> bool {
+    peer.ack_mode != AckMode::Off && (payouts.is_empty() || store.oft_program == Pubkey::default())
+}
+
+// The accounts an ack is paid for and sent with. Shared by lz_receive and retry_failed_message,
+// which acks a message once its retry succeeds.
+pub struct AckAccounts<'a, 'info> {
+    pub store: &'a Account<'info, Store>,
+    pub peer: &'a PeerConfig,
+    pub payer: &'a AccountInfo<'info>,
+    pub fee_vault: &'a AccountInfo<'info>,
+    pub fee_vault_bump: u8,
+    pub system_program: &'a AccountInfo<'info>,
+    pub send_accounts: &'a [AccountInfo<'info>],
+}
+
+fn ack_accounts<'a, 'info>(
+    ctx: &'a Context<'_, '_, '_, 'info, LzReceive<'info>>,
+    send_accounts: &'a [AccountInfo<'info>],
+) -> AckAccounts<'a, 'info> {
+    AckAccounts {
+        store: &ctx.accounts.store,
+        peer: &ctx.accounts.peer,
+        payer: ctx.accounts.payer.as_ref(),
+        fee_vault: ctx.accounts.fee_vault.as_ref(),
+        fee_vault_bump: ctx.bumps.fee_vault,
+        system_program: ctx.accounts.system_program.as_ref(),
+        send_accounts,
+    }
+}
+
+// Acknowledges the message `guid` to its source chain. With AckMode::FeeVault an ack the vault
+// can't pay for is skipped rather than failing the receive.
+pub fn send_ack(
+    accounts: &AckAccounts,
+    src_eid: u32,
+    guid: [u8; 32],
+    request_id: u64,
+    status: u8,
+    pool_id: u64,
+    bet: [u8; 32],
+) -> Result<()> {
+    if accounts.peer.ack_mode == AckMode::FeeVault && !reimburse_from_fee_vault(accounts)? {
+        msg!("Fee vault can't cover the ack for message {:?}", guid);
+        return Ok(());
+    }
+
+    let ack = msg_codec::BetlifyMessage::ActionAck { guid, status, pool_id, bet };
+    let clock = Clock::get()?;
+    let message = msg_codec::encode_betlify_message(request_id, clock.unix_timestamp as u64, &ack);
+    send_to_source(accounts.store, accounts.peer, accounts.send_accounts, src_eid, message)
+}
+
+// Pays the peer's response fee from the fee vault to the payer, keeping the vault rent exempt.
+// Returns false if the vault doesn't hold enough.
+fn reimburse_from_fee_vault(accounts: &AckAccounts) -> Result<bool> {
+    let fee = accounts.peer.response_fee;
+    let reserve = Rent::get()?.minimum_balance(0);
+    if accounts.fee_vault.lamports() < fee.saturating_add(reserve) {
+        return Ok(false);
+    }
+
+    let store_key = accounts.store.key();
+    let seeds: &[&[u8]] = &[FEE_VAULT_SEED, store_key.as_ref(), &[accounts.fee_vault_bump]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            accounts.system_program.clone(),
+            system_program::Transfer { from: accounts.fee_vault.clone(), to: accounts.payer.clone() },
+            &[seeds],
+        ),
+        fee,
+    )?;
+    Ok(true)
+}
+
+// Keeps a failed message in a FailedMessage PDA keyed by its guid until it is retried
+fn store_failed_message(ctx: &Context<LzReceive>, params: &LzReceiveParams, error_code: u32) -> Result<()> {
+    let store_key = ctx.accounts.store.key();
+    let bump = ctx.bumps.failed_message;
+    let seeds: &[&[u8]] = &[FAILED_MESSAGE_SEED, store_key.as_ref(), &params.guid, &[bump]];
+    create_pda(
+        ctx.program_id,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.failed_message.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        seeds,
+        FailedMessage::space(params.message.len()),
+    )?;
+
+    let failed_message = FailedMessage {
+        src_eid: params.src_eid,
+        sender: params.sender,
+        nonce: params.nonce,
+        guid: params.guid,
+        error_code,
+        message: params.message.clone(),
+        bump,
+    };
+    let mut data = ctx.accounts.failed_message.try_borrow_mut_data()?;
+    failed_message.try_serialize(&mut &mut data[..])
+}
+
+fn error_code_of(err: &Error) -> u32 {
+    match err {
+        Error::AnchorError(err) => err.error_code_number,
+        Error::ProgramError(err) => match err.program_error {
+            ProgramError::Custom(code) => code,
+            _ => 0,
+        },
+    }
+}
+
+// Sends a response back to the peer that originated the message being received.
+// The payer covers the peer's configured response fee.
+fn send_to_source(
+    store: &Account<Store>,
+    peer: &PeerConfig,
+    accounts: &[AccountInfo],
+    dst_eid: u32,
+    message: Vec<u8>,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[STORE_SEED, &[store.bump]];
+    let send_params = SendParams {
+        dst_eid,
+        receiver: peer.peer_address,
+        message,
+        options: peer.enforced_options.combine_options(&None::<Vec<u8>>, &Vec::new())?,
+        native_fee: peer.response_fee,
+        lz_token_fee: 0,
+    };
+    oapp::endpoint_cpi::send(ENDPOINT_ID, store.key(), accounts, seeds, send_params)?;
+    Ok(())
+}
+
+// Sends the payout in the stake token from the pool vault to the bettor on the source chain. The
+// amount is already free of dust, so nothing is lost to min_amount_ld.
+// The OFT send accounts are (peer, oft_store, token_source, ...).
+fn pay_out_with_oft<'info>(
+    program_id: &Pubkey,
+    store: &Account<'info, Store>,
+    peer: &PeerConfig,
+    oft_accounts: &[AccountInfo<'info>],
+    dst_eid: u32,
+    payout: Payout,
+) -> Result<()> {
+    let (pool_vault, _) = pda::pool_vault(&payout.pool).address(program_id);
+    require!(oft_accounts.len() > 2, BetlifyError::InvalidPayoutAccounts);
+    require_keys_eq!(oft_accounts[1].key(), store.oft_store, BetlifyError::InvalidPayoutAccounts);
+    require_keys_eq!(oft_accounts[2].key(), pool_vault, BetlifyError::InvalidPayoutAccounts);
+
+    let seeds: &[&[u8]] = &[STORE_SEED, &[store.bump]];
+    oft::send(
+        store.oft_program,
+        store.to_account_info(),
+        oft_accounts,
+        seeds,
+        oft::OftSendParams {
+            dst_eid,
+            to: payout.recipient,
+            amount_ld: payout.amount,
+            min_amount_ld: payout.amount,
+            options: Vec::new(),
+            compose_msg: None,
+            native_fee: peer.response_fee,
+            lz_token_fee: 0,
+        },
+    )
+}
+
+#[error_code(offset = 6200)]
 pub enum BetlifyError {
     #[msg("Missing pool account")] MissingPoolAccount,
     #[msg("Missing bet account")] MissingBetAccount,
@@ -146,4 +960,494 @@ pub enum BetlifyError {
     #[msg("Missing system program")] MissingSystemProgram,
     #[msg("Missing rent sysvar")] MissingRentSysvar,
     #[msg("Failed to decode message")] InvalidMessage,
+    #[msg("Sender is not allowed to resolve this pool")] UnauthorizedResolver,
+    #[msg("Nothing to claim")] NothingToClaim,
+    #[msg("Bet must be funded through the stake OFT")] UnfundedBet,
+    #[msg("Invalid payout accounts")] InvalidPayoutAccounts,
+    #[msg("Pool already exists")] PoolAlreadyExists,
+    #[msg("Peer may not send this message type")] MessageTypeNotAllowed,
+    #[msg("Peer exceeded its inbound message rate limit")] MessageRateLimited,
+    #[msg("Peer exceeded its inbound stake rate limit")] StakeRateLimited,
+    #[msg("Missing identity link account")] MissingIdentityLinkAccount,
+    #[msg("Invalid identity link account")] InvalidIdentityLinkAccount,
+    #[msg("Missing creator pool index account")] MissingCreatorPoolIndexAccount,
+    #[msg("Invalid creator pool index account")] InvalidCreatorPoolIndexAccount,
+    #[msg("Missing accounts for Endpoint::clear")] MissingClearAccounts,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use std::collections::HashMap;
+
+    // Runs messages like lz_receive once their accounts are verified: the accounts a message may
+    // create start out empty, and nothing is persisted unless every action succeeds
+    struct Receiver {
+        store: Account<'static, Store>,
+        accounts: HashMap<Pubkey, AccountInfo<'static>>,
+    }
+
+    impl Receiver {
+        fn new() -> Self {
+            set_now(NOW);
+            Receiver { store: store_account(&store()), accounts: HashMap::new() }
+        }
+
+        fn receive(&mut self, message: msg_codec::BetlifyMessage) -> Result<Vec<Payout>> {
+            let actions = message.actions();
+            let required = required_accounts(actions, self.store.pools_count);
+            let infos: Vec<_> = required.iter().map(|required| self.info(required)).collect();
+            let infos: &'static [AccountInfo<'static>] = Box::leak(infos.into_boxed_slice());
+            let payer = signer_info(Pubkey::new_unique());
+            let system_program = account_info(system_program::ID, Pubkey::default(), 0, Vec::new());
+
+            let snapshot = (*self.store).clone();
+            let store = &mut self.store;
+            let result =
+                load_message_accounts(&crate::ID, store_key(), SRC_EID, &required, infos, &payer, &system_program)
+                    .and_then(|mut accounts| {
+                        let payouts = execute_actions(store, &mut accounts, SRC_EID, actions)?;
+                        accounts.exit(&crate::ID)?;
+                        Ok(payouts)
+                    });
+            if result.is_err() {
+                self.store.set_inner(snapshot);
+            }
+            result
+        }
+
+        fn info(&mut self, required: &RequiredAccount) -> AccountInfo<'static> {
+            let (address, _) = required.account.address(&crate::ID, store_key(), SRC_EID);
+            if let Some(info) = self.accounts.get(&address) {
+                return info.clone();
+            }
+            if !required.init {
+                return account_info(address, system_program::ID, 0, Vec::new());
+            }
+            let mut data = vec![0; required.account.space()];
+            data[..8].copy_from_slice(&required.account.discriminator());
+            let info = account_info(address, crate::ID, 1_000_000, data);
+            self.accounts.insert(address, info.clone());
+            info
+        }
+
+        fn account<T: AccountDeserialize>(&self, account: MessageAccount) -> T {
+            let (address, _) = account.address(&crate::ID, store_key(), SRC_EID);
+            read(&self.accounts[&address])
+        }
+
+        fn pool(&self, pool_id: u64) -> BetPool {
+            self.account(MessageAccount::Pool { pool_id })
+        }
+
+        fn bet(&self, pool_id: u64, authority: [u8; 20]) -> Bet {
+            self.account(MessageAccount::Bet { pool_id, user: msg_codec::remote_user(authority) })
+        }
+
+        fn update_pool(&mut self, pool_id: u64, update: impl FnOnce(&mut BetPool)) {
+            let (address, _) = MessageAccount::Pool { pool_id }.address(&crate::ID, store_key(), SRC_EID);
+            let mut pool = self.pool(pool_id);
+            update(&mut pool);
+            pool.try_serialize(&mut &mut self.accounts[&address].data.borrow_mut()[..]).unwrap();
+        }
+    }
+
+    // Pool 0 created by evm_user(9), with 100 on option 0 from user 1 and 300 and 100 on option 1
+    // from users 2 and 3, resolved to option 1
+    fn resolved_pool() -> Receiver {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        receiver.receive(place_bet(evm_user(1), 0, 0, 100)).unwrap();
+        receiver.receive(place_bet(evm_user(2), 0, 1, 300)).unwrap();
+        receiver.receive(place_bet(evm_user(3), 0, 1, 100)).unwrap();
+        set_now(NOW + 2 * HOUR);
+        receiver.receive(resolve(evm_user(9), 0, 1)).unwrap();
+        receiver
+    }
+
+    fn with_times(mut message: msg_codec::BetlifyMessage, start: i64, lock: i64, end: i64) -> msg_codec::BetlifyMessage {
+        if let msg_codec::BetlifyMessage::CreatePool { start_time, lock_time, end_time, .. } = &mut message {
+            (*start_time, *lock_time, *end_time) = (start, lock, end);
+        }
+        message
+    }
+
+    #[test]
+    fn create_pool_takes_the_next_global_id() {
+        let mut receiver = Receiver::new();
+        receiver.store.pools_count = 3;
+
+        assert!(receiver.receive(create_pool(evm_user(1), 42)).unwrap().is_empty());
+
+        let pool = receiver.pool(3);
+        assert_eq!(pool.id, 3);
+        assert_eq!(pool.creator, Pubkey::default());
+        assert_eq!(pool.origin_eid, SRC_EID);
+        assert_eq!(pool.remote_creator, msg_codec::remote_user(evm_user(1)));
+        assert_eq!(pool.question, "Who will win?");
+        assert_eq!(pool.option_amounts, vec![0, 0]);
+        assert_eq!(pool.resolution_source, "espn.com");
+        assert_eq!(pool.resolution_deadline, NOW + 2 * HOUR + BetPool::RESOLUTION_WINDOW);
+        assert!(pool.status == PoolStatus::Open);
+        assert_eq!(receiver.store.pools_count, 4);
+
+        let creator = msg_codec::remote_user(evm_user(1));
+        let index: CreatorPoolIndex = receiver.account(MessageAccount::CreatorPoolIndex { creator, creator_pool_id: 42 });
+        assert_eq!((index.origin_eid, index.creator_pool_id, index.pool_id), (SRC_EID, 42, 3));
+    }
+
+    #[test]
+    fn create_pool_rejects_invalid_params() {
+        let mut receiver = Receiver::new();
+        let err = receiver.receive(with_times(create_pool(evm_user(1), 1), NOW, NOW + 2 * HOUR, NOW + HOUR));
+        assert_eq!(err.unwrap_err(), CreatePoolError::InvalidTiming.into());
+        let err = receiver.receive(with_times(create_pool(evm_user(1), 1), NOW, NOW + 60, NOW + 60));
+        assert_eq!(err.unwrap_err(), CreatePoolError::DurationTooShort.into());
+        assert_eq!(receiver.store.pools_count, 0);
+    }
+
+    #[test]
+    fn create_pool_rejects_overflowing_times_instead_of_panicking() {
+        let mut receiver = Receiver::new();
+        // end_time - start_time overflows
+        let err = receiver.receive(with_times(create_pool(evm_user(1), 1), i64::MIN, NOW, NOW + HOUR));
+        assert_eq!(err.unwrap_err(), CreatePoolError::InvalidTiming.into());
+        // end_time + RESOLUTION_WINDOW overflows
+        let err = receiver.receive(with_times(create_pool(evm_user(1), 2), i64::MAX - HOUR, i64::MAX, i64::MAX));
+        assert_eq!(err.unwrap_err(), CreatePoolError::InvalidTiming.into());
+
+        receiver.store.pools_count = u64::MAX;
+        let err = receiver.receive(create_pool(evm_user(1), 3));
+        assert_eq!(err.unwrap_err(), CreatePoolError::TooManyPools.into());
+        assert_eq!(receiver.store.pools_count, u64::MAX);
+    }
+
+    #[test]
+    fn create_pool_rejects_a_reused_creator_pool_id() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(1), 42)).unwrap();
+        let err = receiver.receive(create_pool(evm_user(1), 42));
+        assert_eq!(err.unwrap_err(), BetlifyError::PoolAlreadyExists.into());
+        assert_eq!(receiver.store.pools_count, 1);
+    }
+
+    #[test]
+    fn creator_pool_ids_are_per_creator() {
+        // Every user of an adapter sends through the same peer, so ids only clash for the same creator
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(1), 42)).unwrap();
+        receiver.receive(create_pool(evm_user(2), 42)).unwrap();
+        assert_eq!(receiver.store.pools_count, 2);
+
+        let creator = msg_codec::remote_user(evm_user(2));
+        let index: CreatorPoolIndex = receiver.account(MessageAccount::CreatorPoolIndex { creator, creator_pool_id: 42 });
+        assert_eq!(index.pool_id, 1);
+    }
+
+    #[test]
+    fn only_the_remote_creator_resolves_a_pool() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        receiver.receive(place_bet(evm_user(1), 0, 0, 100)).unwrap();
+
+        let err = receiver.receive(resolve(evm_user(9), 0, 1)).unwrap_err();
+        assert_eq!(err, ResolveMarketError::MarketNotEnded.into());
+        set_now(NOW + 2 * HOUR);
+        let err = receiver.receive(resolve(evm_user(1), 0, 1)).unwrap_err();
+        assert_eq!(err, BetlifyError::UnauthorizedResolver.into());
+        assert!(!receiver.pool(0).is_result_set);
+
+        receiver.receive(resolve(evm_user(9), 0, 1)).unwrap();
+        let pool = receiver.pool(0);
+        assert!(pool.status == PoolStatus::Resolved);
+        assert_eq!((pool.winning_option, pool.result), (1, 1));
+        assert_eq!((pool.evidence_uri.as_str(), pool.evidence_hash), ("ipfs://evidence", [7; 32]));
+        let err = receiver.receive(resolve(evm_user(9), 0, 0)).unwrap_err();
+        assert_eq!(err, ResolveMarketError::ResultAlreadySet.into());
+    }
+
+    #[test]
+    fn pools_unresolved_by_the_deadline_are_refunded() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        receiver.receive(place_bet(evm_user(1), 0, 0, 100)).unwrap();
+
+        set_now(receiver.pool(0).resolution_deadline);
+        let err = receiver.receive(resolve(evm_user(9), 0, 1)).unwrap_err();
+        assert_eq!(err, ResolveMarketError::ResolutionDeadlinePassed.into());
+        // Anyone may void the pool now, and the bettor claims the stake back cross-chain
+        let (address, _) = MessageAccount::Pool { pool_id: 0 }.address(&crate::ID, store_key(), SRC_EID);
+        let info: &'static AccountInfo<'static> = Box::leak(Box::new(receiver.accounts[&address].clone()));
+        let mut accounts = ExpireUnresolved { bet_pool: Account::try_from(info).unwrap() };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        ExpireUnresolved::apply(&mut ctx, &ExpireUnresolvedParams {}).unwrap();
+        accounts.exit(&crate::ID).unwrap();
+        assert!(receiver.pool(0).status == PoolStatus::Voided);
+
+        assert_eq!(receiver.receive(claim(evm_user(1), 0)).unwrap()[0].amount, 100);
+    }
+
+    #[test]
+    fn claims_pay_winners_pro_rata_once() {
+        let mut receiver = resolved_pool();
+        let (pool, _) = MessageAccount::Pool { pool_id: 0 }.address(&crate::ID, store_key(), SRC_EID);
+
+        let payouts = receiver.receive(claim(evm_user(2), 0)).unwrap();
+        let recipient = msg_codec::remote_user(evm_user(2));
+        assert_eq!(payouts, vec![Payout { pool_id: 0, pool, recipient, amount: 375 }]);
+        assert!(receiver.bet(0, evm_user(2)).claimed);
+        assert_eq!(receiver.receive(claim(evm_user(3), 0)).unwrap()[0].amount, 125);
+
+        let err = receiver.receive(claim(evm_user(2), 0)).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::AlreadyClaimed.into());
+        // A losing claim fails as a whole, so the bet isn't marked claimed either
+        let err = receiver.receive(claim(evm_user(1), 0)).unwrap_err();
+        assert_eq!(err, BetlifyError::NothingToClaim.into());
+        assert!(!receiver.bet(0, evm_user(1)).claimed);
+    }
+
+    #[test]
+    fn pools_resolved_to_an_option_nobody_backed_refund_every_stake() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        receiver.receive(place_bet(evm_user(1), 0, 0, 100)).unwrap();
+        receiver.receive(place_bet(evm_user(2), 0, 0, 300)).unwrap();
+        set_now(NOW + 2 * HOUR);
+        receiver.receive(resolve(evm_user(9), 0, 1)).unwrap();
+
+        assert_eq!(receiver.receive(claim(evm_user(1), 0)).unwrap()[0].amount, 100);
+        assert_eq!(receiver.receive(claim(evm_user(2), 0)).unwrap()[0].amount, 300);
+        let err = receiver.receive(claim(evm_user(1), 0)).unwrap_err();
+        assert_eq!(err, ClaimWinningsError::AlreadyClaimed.into());
+    }
+
+    #[test]
+    fn peers_only_send_the_message_types_they_allow() {
+        let bets_only = PeerConfig { allowed_msg_types: 1 << msg_codec::PLACE_BET_TYPE, ..peer([1; 32]) };
+        let bet = place_bet(evm_user(1), 0, 0, 100);
+        check_inbound(&store(), &bets_only, SRC_EID, bet.actions()).unwrap();
+        let pool = create_pool(evm_user(9), 1);
+        let err = check_inbound(&store(), &bets_only, SRC_EID, pool.actions()).unwrap_err();
+        assert_eq!(err, BetlifyError::MessageTypeNotAllowed.into());
+        // Every item of a batch must be allowed
+        let batch = msg_codec::BetlifyMessage::Batch(msg_codec::BatchItems(vec![bet, claim(evm_user(1), 0)]));
+        let err = check_inbound(&store(), &bets_only, SRC_EID, batch.actions()).unwrap_err();
+        assert_eq!(err, BetlifyError::MessageTypeNotAllowed.into());
+
+        // An empty mask accepts nothing
+        let none = PeerConfig { allowed_msg_types: 0, ..peer([1; 32]) };
+        let err = check_inbound(&store(), &none, SRC_EID, pool.actions()).unwrap_err();
+        assert_eq!(err, BetlifyError::MessageTypeNotAllowed.into());
+    }
+
+    #[test]
+    fn messages_only_the_program_sends_are_rejected() {
+        let mut receiver = Receiver::new();
+        let user = msg_codec::remote_user(evm_user(1));
+        for message in [
+            msg_codec::BetlifyMessage::PayoutIssued { pool_id: 0, recipient: user, amount: 100 },
+            msg_codec::BetlifyMessage::MessageFailed { guid: [0; 32], error_code: 6000 },
+            msg_codec::BetlifyMessage::ActionAck { guid: [0; 32], status: 0, pool_id: 0, bet: [0; 32] },
+        ] {
+            assert_eq!(receiver.receive(message).unwrap_err(), MyOAppError::InvalidMessageType.into());
+        }
+    }
+
+    #[test]
+    fn link_wallet_records_the_users_solana_key() {
+        let mut receiver = Receiver::new();
+        let user = msg_codec::remote_user(evm_user(1));
+        for key in [Pubkey::new_unique(), Pubkey::new_unique()] {
+            let link_wallet = msg_codec::BetlifyMessage::LinkWallet { authority: evm_user(1), solana_pubkey: key.to_bytes() };
+            assert!(receiver.receive(link_wallet).unwrap().is_empty());
+
+            // Relinking moves the link to the new key
+            let link: IdentityLink = receiver.account(MessageAccount::IdentityLink { user });
+            assert_eq!((link.src_eid, link.user, link.solana_pubkey), (SRC_EID, user, key));
+        }
+    }
+
+    #[test]
+    fn error_codes_name_one_error() {
+        let codes = [
+            error_code_of(&MyOAppError::InvalidMessageType.into()),
+            error_code_of(&msg_codec::MsgCodecError::BodyTooShort.into()),
+            error_code_of(&BetlifyError::MissingPoolAccount.into()),
+            error_code_of(&CreatePoolError::InvalidTiming.into()),
+            error_code_of(&PlaceBetError::BettingClosed.into()),
+            error_code_of(&ClaimWinningsError::ResultNotSet.into()),
+            error_code_of(&RefundBetError::PoolNotVoided.into()),
+        ];
+        let distinct: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(distinct.len(), codes.len());
+        assert_eq!(codes[0], 6000);
+    }
+
+    #[test]
+    fn claims_paid_in_tokens_leave_the_dust_in_the_vault() {
+        let mut receiver = resolved_pool();
+        receiver.store.oft_program = Pubkey::new_unique();
+        receiver.store.stake_ld2sd_rate = 10;
+
+        assert_eq!(receiver.receive(claim(evm_user(2), 0)).unwrap()[0].amount, 370);
+        assert_eq!(receiver.receive(claim(evm_user(3), 0)).unwrap()[0].amount, 120);
+    }
+
+    #[test]
+    fn claims_on_voided_pools_refund_the_stake() {
+        let mut receiver = resolved_pool();
+        receiver.update_pool(0, |pool| {
+            pool.status = PoolStatus::Voided;
+            pool.is_result_set = false;
+        });
+        let (pool, _) = MessageAccount::Pool { pool_id: 0 }.address(&crate::ID, store_key(), SRC_EID);
+
+        let payouts = receiver.receive(claim(evm_user(1), 0)).unwrap();
+        let recipient = msg_codec::remote_user(evm_user(1));
+        assert_eq!(payouts, vec![Payout { pool_id: 0, pool, recipient, amount: 100 }]);
+        let err = receiver.receive(claim(evm_user(1), 0)).unwrap_err();
+        assert_eq!(err, RefundBetError::AlreadyRefunded.into());
+    }
+
+    #[test]
+    fn bets_are_kept_per_remote_user() {
+        let receiver = resolved_pool();
+        let (pool, _) = MessageAccount::Pool { pool_id: 0 }.address(&crate::ID, store_key(), SRC_EID);
+        for (user, option, amount) in [(evm_user(1), 0, 100), (evm_user(2), 1, 300), (evm_user(3), 1, 100)] {
+            let bet = receiver.bet(0, user);
+            assert_eq!((bet.pool, bet.option, bet.amount), (pool, option, amount));
+            assert_eq!((bet.user, bet.origin_eid), (msg_codec::remote_user(user), SRC_EID));
+            assert_eq!(bet.authority, Pubkey::default());
+        }
+        assert_eq!(receiver.pool(0).unique_bettors, 3);
+    }
+
+    #[test]
+    fn claims_only_settle_the_claiming_users_bet() {
+        let mut receiver = resolved_pool();
+        // A user without a bet on the pool has nothing to claim, whoever else won
+        let err = receiver.receive(claim(evm_user(4), 0)).unwrap_err();
+        assert_eq!(err, BetlifyError::MissingBetAccount.into());
+
+        receiver.receive(claim(evm_user(2), 0)).unwrap();
+        assert!(receiver.bet(0, evm_user(2)).claimed);
+        assert!(!receiver.bet(0, evm_user(3)).claimed);
+    }
+
+    #[test]
+    fn bets_close_at_the_lock_time() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        receiver.receive(place_bet(evm_user(1), 0, 0, 100)).unwrap();
+        let err = receiver.receive(place_bet(evm_user(1), 0, 1, 100)).unwrap_err();
+        assert_eq!(err, PlaceBetError::DuplicateBet.into());
+        let err = receiver.receive(place_bet(evm_user(2), 0, 2, 100)).unwrap_err();
+        assert_eq!(err, PlaceBetError::InvalidOption.into());
+
+        set_now(NOW + HOUR);
+        let err = receiver.receive(place_bet(evm_user(2), 0, 1, 100)).unwrap_err();
+        assert_eq!(err, PlaceBetError::BettingClosed.into());
+        assert_eq!((receiver.pool(0).pool_amount, receiver.pool(0).unique_bettors), (100, 1));
+    }
+
+    #[test]
+    fn bets_must_be_positive_and_fit_the_pool_totals() {
+        let mut receiver = Receiver::new();
+        receiver.receive(create_pool(evm_user(9), 1)).unwrap();
+        let err = receiver.receive(place_bet(evm_user(1), 0, 1, 0)).unwrap_err();
+        assert_eq!(err, PlaceBetError::ZeroAmount.into());
+
+        receiver.receive(place_bet(evm_user(1), 0, 1, u64::MAX)).unwrap();
+        let err = receiver.receive(place_bet(evm_user(2), 0, 0, 1)).unwrap_err();
+        assert_eq!(err, PlaceBetError::PoolTotalsOverflow.into());
+        assert_eq!(receiver.pool(0).pool_amount, u64::MAX);
+    }
+
+    // The fixed lz_receive accounts for a message from `peer`, with `vault_lamports` in the fee vault
+    fn receive_accounts(peer: PeerConfig, vault_lamports: u64) -> LzReceive<'static> {
+        let leak = |info: AccountInfo<'static>| -> &'static AccountInfo<'static> {
+            Box::leak(Box::new(info))
+        };
+        let nonce = Nonce { bump: 0, max_received_nonce: 0 };
+        let response_accounts = ResponseAccounts { bump: 0, routes: vec![] };
+        LzReceive {
+            payer: signer(Pubkey::new_unique()),
+            peer: load(program_account(Pubkey::new_unique(), &peer, 8 + PeerConfig::INIT_SPACE)),
+            store: store_account(&store()),
+            nonce_account: load(program_account(Pubkey::new_unique(), &nonce, Nonce::SIZE)),
+            failed_message: UncheckedAccount::try_from(leak(account_info(
+                Pubkey::new_unique(),
+                system_program::ID,
+                0,
+                Vec::new(),
+            ))),
+            fee_vault: SystemAccount::try_from(leak(account_info(
+                Pubkey::new_unique(),
+                system_program::ID,
+                vault_lamports,
+                Vec::new(),
+            )))
+            .unwrap(),
+            response_accounts: load(program_account(
+                Pubkey::new_unique(),
+                &response_accounts,
+                ResponseAccounts::space(&[]),
+            )),
+            system_program: system_program(),
+        }
+    }
+
+    #[test]
+    fn acks_follow_the_peers_ack_mode() {
+        let off = peer([1; 32]);
+        let fee_vault = PeerConfig { ack_mode: AckMode::FeeVault, ..peer([1; 32]) };
+        let oft_store = Store { oft_program: Pubkey::new_unique(), ..store() };
+        let pool = Pubkey::new_unique();
+        let payout = || Payout { pool_id: 0, pool, recipient: [1; 32], amount: 100 };
+
+        assert!(!acknowledges(&off, &store(), &[]));
+        assert!(!acknowledges(&off, &store(), &[payout()]));
+        assert!(acknowledges(&fee_vault, &store(), &[]));
+        // PayoutIssued messages are acknowledged, but tokens sent through the OFT aren't
+        assert!(acknowledges(&fee_vault, &store(), &[payout()]));
+        assert!(acknowledges(&fee_vault, &oft_store, &[]));
+        assert!(!acknowledges(&fee_vault, &oft_store, &[payout()]));
+    }
+
+    #[test]
+    fn the_fee_vault_reimburses_the_payer_and_stays_rent_exempt() {
+        set_now(NOW);
+        let fee = 5_000;
+        let reserve = Rent::default().minimum_balance(0);
+        let peer = PeerConfig { ack_mode: AckMode::FeeVault, response_fee: fee, ..peer([1; 32]) };
+
+        let mut accounts = receive_accounts(peer.clone(), reserve + fee);
+        let payer_lamports = accounts.payer.lamports();
+        let ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        assert!(reimburse_from_fee_vault(&ack_accounts(&ctx, &[])).unwrap());
+        assert_eq!(accounts.fee_vault.lamports(), reserve);
+        assert_eq!(accounts.payer.lamports(), payer_lamports + fee);
+
+        // A vault that would drop below the rent reserve pays nothing
+        take_invoked();
+        let mut accounts = receive_accounts(peer, reserve + fee - 1);
+        let ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        assert!(!reimburse_from_fee_vault(&ack_accounts(&ctx, &[])).unwrap());
+        assert_eq!(accounts.fee_vault.lamports(), reserve + fee - 1);
+        assert!(take_invoked().is_empty());
+    }
+
+    #[test]
+    fn acks_the_fee_vault_cannot_pay_for_are_skipped() {
+        set_now(NOW);
+        let peer = PeerConfig { ack_mode: AckMode::FeeVault, response_fee: 5_000, ..peer([1; 32]) };
+        let mut accounts = receive_accounts(peer, 0);
+        let ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+
+        take_invoked();
+        send_ack(&ack_accounts(&ctx, &[]), SRC_EID, [9; 32], 1, msg_codec::ACK_EXECUTED, 0, [0; 32]).unwrap();
+        // Neither the reimbursement nor the Endpoint send went out
+        assert!(take_invoked().is_empty());
+    }
 }
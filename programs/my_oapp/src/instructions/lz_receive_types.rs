@@ -7,6 +7,8 @@ use msg_codec;
 pub struct LzReceiveTypes<'info> {
     #[account(seeds = [STORE_SEED], bump = store.bump)]
     pub store: Account<'info, Store>,
+    #[account(seeds = [RESPONSE_ACCOUNTS_SEED, &store.key().to_bytes()], bump = response_accounts.bump)]
+    pub response_accounts: Account<'info, ResponseAccounts>,
 }
 
 impl LzReceiveTypes<'_> {
@@ -14,150 +16,404 @@ impl LzReceiveTypes<'_> {
         ctx: &Context<LzReceiveTypes>,
         params: &LzReceiveParams,
     ) -> Result<Vec<LzAccount>> {
-        let store = ctx.accounts.store.key();
-        let peer_seeds = [PEER_SEED, &store.to_bytes(), &params.src_eid.to_be_bytes()];
-        let (peer, _) = Pubkey::find_program_address(&peer_seeds, ctx.program_id);
-
-        let mut accounts = vec![
-            // 0. payer (placeholder, Executor fills this)
-            LzAccount {
-                pubkey: Pubkey::default(),
-                is_signer: true,
-                is_writable: true,
-            },
-            // 1. peer PDA
-            LzAccount {
-                pubkey: peer,
-                is_signer: false,
-                is_writable: false,
-            },
-            // 2. store PDA
-            LzAccount {
-                pubkey: store,
-                is_signer: false,
-                is_writable: true,
-            },
+        lz_receive_accounts(
+            ctx.program_id,
+            ctx.accounts.store.key(),
+            &ctx.accounts.store,
+            &ctx.accounts.response_accounts,
+            params,
+        )
+    }
+}
+
+// The accounts lz_receive takes for a message: its fixed accounts, the accounts the message acts
+// on, the accounts for Endpoint::clear and the accounts for responses to the source chain. Pools the
+// message creates are numbered from the Store's pool count, so a pool created in between makes
+// lz_receive revert until the list is fetched again.
+pub fn lz_receive_accounts(
+    program_id: &Pubkey,
+    store: Pubkey,
+    store_state: &Store,
+    response_accounts: &ResponseAccounts,
+    params: &LzReceiveParams,
+) -> Result<Vec<LzAccount>> {
+    let (peer, _) = pda::peer(&store, params.src_eid).address(program_id);
+    let (nonce_account, _) = pda::nonce(&store, params.src_eid, &params.sender).address(program_id);
+    let (failed_message, _) = pda::failed_message(&store, &params.guid).address(program_id);
+    let (fee_vault, _) = pda::fee_vault(&store).address(program_id);
+    let (response_accounts_pda, _) = pda::response_accounts(&store).address(program_id);
+
+    let mut accounts = vec![
+        // 0. payer (placeholder, Executor fills this)
+        LzAccount {
+            pubkey: Pubkey::default(),
+            is_signer: true,
+            is_writable: true,
+        },
+        // 1. peer PDA (rate limits are updated)
+        LzAccount {
+            pubkey: peer,
+            is_signer: false,
+            is_writable: true,
+        },
+        // 2. store PDA
+        LzAccount {
+            pubkey: store,
+            is_signer: false,
+            is_writable: true,
+        },
+        // 3. inbound nonce PDA for (src_eid, sender)
+        LzAccount {
+            pubkey: nonce_account,
+            is_signer: false,
+            is_writable: true,
+        },
+        // 4. failed message PDA for the guid (only created if a non-blocking receive fails)
+        LzAccount {
+            pubkey: failed_message,
+            is_signer: false,
+            is_writable: true,
+        },
+        // 5. store fee vault (reimburses the payer for acks)
+        LzAccount {
+            pubkey: fee_vault,
+            is_signer: false,
+            is_writable: true,
+        },
+        // 6. response accounts registry (how to split the response accounts below)
+        LzAccount {
+            pubkey: response_accounts_pda,
+            is_signer: false,
+            is_writable: false,
+        },
+        // 7. system_program
+        LzAccount {
+            pubkey: system_program::ID,
+            is_signer: false,
+            is_writable: false,
+        },
+    ];
+
+    // Append the pools, bets and identity links the message acts on, exactly as lz_receive
+    // verifies them. A batch gets the union of its items' accounts, and a message that doesn't
+    // decode gets none, so lz_receive can still clear it.
+    let message = msg_codec::decode_betlify_message(&params.message)
+        .map_err(|err| msg!("Decode error: {}", err))
+        .ok();
+    let actions = message.as_ref().map_or(&[][..], |message| message.actions());
+    for required in required_accounts(actions, store_state.pools_count) {
+        let (pubkey, _) = required.account.address(program_id, store, params.src_eid);
+        accounts.push(LzAccount {
+            pubkey,
+            is_signer: false,
+            is_writable: required.writable,
+        });
+    }
+
+    // Append accounts for Endpoint::clear (replay protection)
+    let clear_accounts = get_accounts_for_clear(
+        oapp::endpoint::ID,
+        &store,
+        params.src_eid,
+        &params.sender,
+        params.nonce,
+    );
+    accounts.extend(clear_accounts);
+
+    // Append the accounts responses are sent with: Endpoint::send for payout notices, acks and
+    // failure notices, then the OFT send accounts when claims are paid out in the stake token
+    if let Some(route) = response_accounts.route(params.src_eid) {
+        accounts.extend(route.send.iter().map(LzAccount::from));
+        let oft_payouts = store_state.oft_program != Pubkey::default()
+            && actions
+                .iter()
+                .any(|action| matches!(action, msg_codec::BetlifyMessage::ClaimWinnings { .. }));
+        if oft_payouts {
+            accounts.extend(route.oft_send.iter().map(LzAccount::from));
+        }
+    }
+
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, claim, create_pool, encoded, evm_user, place_bet, resolve, SENDER, SRC_EID};
+    use anchor_lang::ToAccountMetas;
+
+    const GUID: [u8; 32] = [9; 32];
+    const POOLS_COUNT: u64 = 5;
+
+    fn store() -> Pubkey {
+        Pubkey::find_program_address(&[STORE_SEED], &crate::ID).0
+    }
+
+    fn params(message: &msg_codec::BetlifyMessage) -> LzReceiveParams {
+        LzReceiveParams {
+            src_eid: SRC_EID,
+            sender: SENDER,
+            nonce: 1,
+            guid: GUID,
+            message: encoded(message),
+            extra_data: vec![],
+        }
+    }
+
+    // Derived from the raw seeds in the Accounts constraints rather than through `pda`
+    fn pool_address(pool_id: u64) -> Pubkey {
+        let store = store();
+        let seeds = [b"betpool".as_ref(), store.as_ref(), &pool_id.to_le_bytes()];
+        Pubkey::find_program_address(&seeds, &crate::ID).0
+    }
+
+    fn creator_pool_index_address(creator: [u8; 20], creator_pool_id: u64) -> Pubkey {
+        let creator = msg_codec::remote_user(creator);
+        let store = store();
+        let seeds = [
+            CREATOR_POOL_INDEX_SEED,
+            store.as_ref(),
+            &SRC_EID.to_be_bytes(),
+            &creator,
+            &creator_pool_id.to_le_bytes(),
         ];
+        Pubkey::find_program_address(&seeds, &crate::ID).0
+    }
+
+    fn bet_address(pool_id: u64, authority: [u8; 20]) -> Pubkey {
+        let user = msg_codec::remote_user(authority);
+        let pool = pool_address(pool_id);
+        let seeds = [b"bet".as_ref(), &SRC_EID.to_be_bytes(), &user, pool.as_ref()];
+        Pubkey::find_program_address(&seeds, &crate::ID).0
+    }
+
+    fn identity_link_address(authority: [u8; 20]) -> Pubkey {
+        let user = msg_codec::remote_user(authority);
+        let store = store();
+        let seeds = [IDENTITY_LINK_SEED, store.as_ref(), &SRC_EID.to_be_bytes(), &user];
+        Pubkey::find_program_address(&seeds, &crate::ID).0
+    }
 
-        // Try to decode and dynamically add message-specific accounts
-        match msg_codec::decode_betlify_message(&params.message) {
-            Ok(betlify_msg) => {
-                match betlify_msg {
-                    msg_codec::BetlifyMessage::CreatePool { pool_id, .. } => {
-                        let store = &ctx.accounts.store;
-                        let store_key = store.key();
-                        let seeds = [b"betpool", store_key.as_ref(), &pool_id.to_le_bytes()];
-                        let (bet_pool, _) = Pubkey::find_program_address(&seeds, ctx.program_id);
-
-                        let bet_pool_key = bet_pool.key();
-                        let bet_seeds = [b"bet", params.sender.as_ref(), bet_pool_key.as_ref()];
-                        let (bet_pda, _) = Pubkey::find_program_address(&bet_seeds, ctx.program_id);
-
-                        accounts.extend_from_slice(&[
-                            // 3. bet_pool (to be initialized)
-                            LzAccount {
-                                pubkey: bet_pool,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                            // 4. bet (to be initialized)
-                            LzAccount {
-                                pubkey: bet_pda,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                            // 5. system_program
-                            LzAccount {
-                                pubkey: system_program::ID,
-                                is_signer: false,
-                                is_writable: false,
-                            }
-                        ]);
-                    }
-
-                    msg_codec::BetlifyMessage::PlaceBet { pool_id, .. } => {
-                        let store = ctx.accounts.store.key();
-                        let pool_seeds = [b"betpool", store.as_ref(), &pool_id.to_le_bytes()];
-                        let (bet_pool, _) = Pubkey::find_program_address(&pool_seeds, ctx.program_id);
-
-                        let bet_pool_key = bet_pool.key();
-                        let bet_seeds = [b"bet", params.sender.as_ref(), bet_pool_key.as_ref()];
-                        let (bet_pda, _) = Pubkey::find_program_address(&bet_seeds, ctx.program_id);
-
-                        accounts.extend_from_slice(&[
-                            // 3. bet_pool
-                            LzAccount {
-                                pubkey: bet_pool,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                            // 4. bet (to be initialized)
-                            LzAccount {
-                                pubkey: bet_pda,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                            // 5. system_program
-                            LzAccount {
-                                pubkey: system_program::ID,
-                                is_signer: false,
-                                is_writable: false,
-                            },
-                        ]);
-                    }
-
-                    msg_codec::BetlifyMessage::ResolveMarket { pool_id, .. } => {
-                        let store = ctx.accounts.store.key();
-                        let seeds = [b"betpool", store.as_ref(), &pool_id.to_le_bytes()];
-                        let (bet_pool, _) = Pubkey::find_program_address(&seeds, ctx.program_id);
-
-                        accounts.push(LzAccount {
-                            pubkey: bet_pool,
-                            is_signer: false,
-                            is_writable: true,
-                        });
-                    }
-
-                    msg_codec::BetlifyMessage::ClaimWinnings { pool_id, .. } => {
-                        let store = ctx.accounts.store.key();
-                        let seeds = [b"bet", store.as_ref(), &pool_id.to_le_bytes()];
-                        let (bet_pda, _) = Pubkey::find_program_address(&seeds, ctx.program_id);
-
-                        accounts.extend_from_slice(&[
-                            // bet_pool
-                            LzAccount {
-                                pubkey: bet_pda,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                            // bet
-                            LzAccount {
-                                pubkey: bet_pda,
-                                is_signer: false,
-                                is_writable: true,
-                            },
-                        ]);
-                    }
-                }
-            }
-            Err(err) => {
-                msg!("Decode error: {:?}", err);
-                return Err(BetlifyError::InvalidMessage.into());
-
-            }
+    fn fixed_accounts() -> Vec<AccountMeta> {
+        let store = store();
+        let address = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        crate::accounts::LzReceive {
+            payer: Pubkey::default(),
+            peer: address(&[PEER_SEED, store.as_ref(), &SRC_EID.to_be_bytes()]),
+            store,
+            nonce_account: address(&[NONCE_SEED, store.as_ref(), &SRC_EID.to_be_bytes(), &SENDER]),
+            failed_message: address(&[FAILED_MESSAGE_SEED, store.as_ref(), &GUID]),
+            fee_vault: address(&[FEE_VAULT_SEED, store.as_ref()]),
+            response_accounts: address(&[RESPONSE_ACCOUNTS_SEED, store.as_ref()]),
+            system_program: system_program::ID,
         }
+        .to_account_metas(None)
+    }
+
+    // (pubkey, is_signer, is_writable) of each account, to compare lists
+    type Listed = Vec<(Pubkey, bool, bool)>;
+
+    fn store_state(oft_program: Pubkey) -> Store {
+        Store { pools_count: POOLS_COUNT, oft_program, ..test_utils::store() }
+    }
+
+    fn response_account(byte: u8, is_writable: bool) -> ResponseAccount {
+        ResponseAccount { pubkey: Pubkey::new_from_array([byte; 32]), is_signer: false, is_writable }
+    }
+
+    // Responses to SRC_EID, with the payer placeholder the Endpoint's send library charges
+    fn registry() -> ResponseAccounts {
+        let payer = ResponseAccount { pubkey: Pubkey::default(), is_signer: true, is_writable: true };
+        ResponseAccounts {
+            bump: 255,
+            routes: vec![ResponseRoute {
+                eid: SRC_EID,
+                send: vec![payer, response_account(1, false), response_account(2, true)],
+                oft_send: vec![response_account(3, true), response_account(4, false)],
+            }],
+        }
+    }
+
+    fn message_accounts(message: &msg_codec::BetlifyMessage) -> Vec<(Pubkey, bool)> {
+        let empty = ResponseAccounts { bump: 255, routes: vec![] };
+        let (message_accounts, responses) =
+            lz_receive_accounts_of(message, &store_state(Pubkey::default()), &empty);
+        assert!(responses.is_empty());
+        message_accounts
+    }
 
-        // Append accounts for Endpoint::clear (replay protection)
-        let clear_accounts = get_accounts_for_clear(
-            oapp::endpoint::ID,
-            &store,
-            params.src_eid,
-            &params.sender,
-            params.nonce,
+    // Checks the returned list is LzReceive's accounts, then exactly the message accounts lz_receive
+    // verifies, then the clear accounts, and returns the message and response accounts
+    fn lz_receive_accounts_of(
+        message: &msg_codec::BetlifyMessage,
+        store_state: &Store,
+        response_accounts: &ResponseAccounts,
+    ) -> (Vec<(Pubkey, bool)>, Listed) {
+        let params = params(message);
+        let accounts = lz_receive_accounts(&crate::ID, store(), store_state, response_accounts, &params).unwrap();
+        let list = |accounts: &[LzAccount]| -> Listed {
+            accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect()
+        };
+
+        let fixed = fixed_accounts();
+        let expected: Vec<_> = fixed.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect();
+        assert_eq!(list(&accounts[..fixed.len()]), expected);
+
+        let required = required_accounts(message.actions(), POOLS_COUNT);
+        let (message_accounts, endpoint_accounts) = accounts[fixed.len()..].split_at(required.len());
+        let clear = get_accounts_for_clear(oapp::endpoint::ID, &store(), SRC_EID, &SENDER, 1);
+        let (clear_accounts, responses) = endpoint_accounts.split_at(clear.len());
+        assert_eq!(list(clear_accounts), list(&clear));
+
+        let keys: Vec<Pubkey> = message_accounts.iter().map(|a| a.pubkey).collect();
+        let mut lamports = vec![0u64; keys.len()];
+        let mut data = vec![Vec::<u8>::new(); keys.len()];
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(message_accounts)
+            .zip(lamports.iter_mut().zip(data.iter_mut()))
+            .map(|((key, account), (lamports, data))| {
+                AccountInfo::new(key, false, account.is_writable, lamports, data, &system_program::ID, false, 0)
+            })
+            .collect();
+        verify_message_accounts(&crate::ID, store(), SRC_EID, &required, &infos).unwrap();
+
+        (message_accounts.iter().map(|a| (a.pubkey, a.is_writable)).collect(), list(responses))
+    }
+
+    #[test]
+    fn create_pool_accounts() {
+        // The pool gets the next global id, whatever the creator's own id is
+        assert_eq!(
+            message_accounts(&create_pool(evm_user(1), 42)),
+            vec![(pool_address(POOLS_COUNT), true), (creator_pool_index_address(evm_user(1), 42), true)]
         );
-        accounts.extend(clear_accounts);
+    }
 
-        Ok(accounts)
+    #[test]
+    fn created_pools_get_consecutive_global_ids() {
+        let message = msg_codec::BetlifyMessage::Batch(msg_codec::BatchItems(vec![
+            create_pool(evm_user(1), 42),
+            create_pool(evm_user(1), 7),
+        ]));
+        assert_eq!(
+            message_accounts(&message),
+            vec![
+                (pool_address(POOLS_COUNT), true),
+                (creator_pool_index_address(evm_user(1), 42), true),
+                (pool_address(POOLS_COUNT + 1), true),
+                (creator_pool_index_address(evm_user(1), 7), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn place_bet_accounts() {
+        assert_eq!(
+            message_accounts(&place_bet(evm_user(1), 42, 1, 1000)),
+            vec![(pool_address(42), true), (bet_address(42, evm_user(1)), true)]
+        );
+    }
+
+    #[test]
+    fn resolve_market_accounts() {
+        assert_eq!(message_accounts(&resolve(evm_user(1), 42, 1)), vec![(pool_address(42), true)]);
+    }
+
+    #[test]
+    fn claim_winnings_accounts() {
+        // The bet placed by the same authority, on the pool created with the same id
+        let message = claim(evm_user(1), 42);
+        assert_eq!(
+            message_accounts(&message),
+            vec![(pool_address(42), false), (bet_address(42, evm_user(1)), true)]
+        );
+    }
+
+    #[test]
+    fn link_wallet_accounts() {
+        let message = msg_codec::BetlifyMessage::LinkWallet { authority: evm_user(1), solana_pubkey: [5; 32] };
+        assert_eq!(message_accounts(&message), vec![(identity_link_address(evm_user(1)), true)]);
     }
-}
 
+    #[test]
+    fn outbound_messages_have_no_accounts() {
+        let message = msg_codec::BetlifyMessage::PayoutIssued { pool_id: 42, recipient: [1; 32], amount: 1000 };
+        assert!(message_accounts(&message).is_empty());
+    }
+
+    #[test]
+    fn batch_accounts_are_the_union_of_its_items() {
+        let message = msg_codec::BetlifyMessage::Batch(msg_codec::BatchItems(vec![
+            create_pool(evm_user(1), 1),
+            place_bet(evm_user(1), 42, 1, 1000),
+            place_bet(evm_user(2), 42, 1, 1000),
+            claim(evm_user(1), 42),
+        ]));
+        assert_eq!(
+            message_accounts(&message),
+            vec![
+                (pool_address(POOLS_COUNT), true),
+                (creator_pool_index_address(evm_user(1), 1), true),
+                (pool_address(42), true),
+                (bet_address(42, evm_user(1)), true),
+                (bet_address(42, evm_user(2)), true),
+            ]
+        );
+    }
+
+    fn response_accounts(message: &msg_codec::BetlifyMessage, oft_program: Pubkey) -> Listed {
+        lz_receive_accounts_of(message, &store_state(oft_program), &registry()).1
+    }
+
+    fn listed(accounts: &[ResponseAccount]) -> Listed {
+        accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect()
+    }
+
+    #[test]
+    fn responses_take_the_registered_send_accounts() {
+        // Any message may be acked or, when it fails, answered with a MessageFailed notice
+        let route = registry().routes.remove(0);
+        let claim = claim(evm_user(1), 42);
+        assert_eq!(response_accounts(&place_bet(evm_user(1), 42, 1, 1000), Pubkey::default()), listed(&route.send));
+        assert_eq!(response_accounts(&claim, Pubkey::default()), listed(&route.send));
+    }
+
+    #[test]
+    fn claims_paid_in_tokens_also_take_the_oft_send_accounts() {
+        let route = registry().routes.remove(0);
+        let oft_program = Pubkey::new_from_array([8; 32]);
+        let claim = claim(evm_user(1), 42);
+        let batch =
+            msg_codec::BetlifyMessage::Batch(msg_codec::BatchItems(vec![place_bet(evm_user(2), 42, 1, 1000), claim.clone()]));
+
+        let expected = [listed(&route.send), listed(&route.oft_send)].concat();
+        assert_eq!(response_accounts(&claim, oft_program), expected);
+        assert_eq!(response_accounts(&batch, oft_program), expected);
+        assert_eq!(response_accounts(&place_bet(evm_user(1), 42, 1, 1000), oft_program), listed(&route.send));
+    }
+
+    #[test]
+    fn messages_that_dont_decode_get_the_clear_and_response_accounts_only() {
+        let mut params = params(&place_bet(evm_user(1), 42, 1, 1000));
+        params.message.truncate(params.message.len() - 1);
+        let accounts = lz_receive_accounts(&crate::ID, store(), &store_state(Pubkey::default()), &registry(), &params)
+            .unwrap();
+
+        let list = |accounts: &[LzAccount]| -> Listed {
+            accounts.iter().map(|a| (a.pubkey, a.is_signer, a.is_writable)).collect()
+        };
+        let clear = get_accounts_for_clear(oapp::endpoint::ID, &store(), SRC_EID, &SENDER, 1);
+        let (clear_accounts, responses) = accounts[fixed_accounts().len()..].split_at(clear.len());
+        assert_eq!(list(clear_accounts), list(&clear));
+        assert_eq!(list(responses), listed(&registry().routes[0].send));
+    }
+
+    #[test]
+    fn unregistered_sources_get_no_response_accounts() {
+        let mut registry = registry();
+        registry.routes[0].eid = SRC_EID + 1;
+        let message = place_bet(evm_user(1), 42, 1, 1000);
+        assert!(lz_receive_accounts_of(&message, &store_state(Pubkey::default()), &registry).1.is_empty());
+    }
+}
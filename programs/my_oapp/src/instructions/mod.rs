@@ -4,10 +4,25 @@ pub mod lz_receive;
 pub mod lz_receive_types;
 pub mod quote_send;
 pub mod set_peer_config;
+pub mod set_store_config;
 pub mod create_pool;
 pub mod place_bet;
 pub mod resolve_market;
 pub mod claim_winnings;
+pub mod expire_unresolved;
+pub mod refund_bet;
+pub mod cash_out;
+pub mod close_betting;
+pub mod update_pool_schedule;
+pub mod init_pool_vault;
+pub mod lz_compose;
+pub mod lz_compose_types;
+pub mod next_nonce;
+pub mod skip_inbound_nonce;
+pub mod set_pause;
+pub mod retry_failed_message;
+pub mod withdraw_fee_vault;
+pub mod set_response_accounts;
 
 
 pub use send::*;
@@ -16,7 +31,22 @@ pub use lz_receive::*;
 pub use lz_receive_types::*;
 pub use quote_send::*;
 pub use set_peer_config::*;
+pub use set_store_config::*;
 pub use create_pool::*;
 pub use place_bet::*;
 pub use resolve_market::*;
 pub use claim_winnings::*;
+pub use expire_unresolved::*;
+pub use refund_bet::*;
+pub use cash_out::*;
+pub use close_betting::*;
+pub use update_pool_schedule::*;
+pub use init_pool_vault::*;
+pub use lz_compose::*;
+pub use lz_compose_types::*;
+pub use next_nonce::*;
+pub use skip_inbound_nonce::*;
+pub use set_pause::*;
+pub use retry_failed_message::*;
+pub use withdraw_fee_vault::*;
+pub use set_response_accounts::*;
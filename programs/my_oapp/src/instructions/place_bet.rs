@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use crate::state::bet_pool::BetPool;
 use crate::state::bet::Bet;
+use crate::state::store::Store;
+use crate::errors::MyOAppError;
+use crate::{BET_SEED, POOL_VAULT_SEED, STORE_SEED};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct PlaceBetParams {
@@ -17,17 +21,26 @@ pub struct PlaceBet<'info> {
     #[account(
         init_if_needed,
         payer = user,
-        space =  Bet::INIT_SPACE, // 8 + 4 + 64 + 32 + 32 + 1 + 8 + 1 + 1, // 4+64 for authority string
-        seeds = [b"bet", user.key().as_ref(), bet_pool.key().as_ref()],
+        space = 8 + Bet::INIT_SPACE,
+        seeds = [BET_SEED, user.key().as_ref(), bet_pool.key().as_ref()],
         bump
     )]
     pub bet: Account<'info, Bet>,
     #[account(mut)]
     pub user: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    /// Vault the stake is paid into, required once stakes are held in tokens
+    #[account(mut, seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()], bump)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    /// Stake token account of the user the stake is paid from
+    #[account(mut)]
+    pub user_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
     pub system_program: Program<'info, System>,
 }
 
-#[error_code]
+#[error_code(offset = 6400)]
 pub enum PlaceBetError {
     #[msg("Betting is not open for this pool")] 
     BettingClosed,
@@ -35,30 +48,134 @@ pub enum PlaceBetError {
     DuplicateBet,
     #[msg("Invalid option")] 
     InvalidOption,
+    #[msg("Bet amount must be positive")] 
+    ZeroAmount,
+    #[msg("Pool totals overflow")] 
+    PoolTotalsOverflow,
 }
 
 impl<'info> PlaceBet<'info> {
     pub fn apply(ctx: &mut Context<Self>, params: &PlaceBetParams) -> Result<()> {
-        let bet = &mut ctx.accounts.bet;
-        let pool = &mut ctx.accounts.bet_pool;
+        require!(!ctx.accounts.store.paused, MyOAppError::Paused);
+        let pool_key = ctx.accounts.bet_pool.key();
         let clock = Clock::get()?;
+        record_bet(
+            &mut ctx.accounts.bet_pool,
+            pool_key,
+            &mut ctx.accounts.bet,
+            params.option,
+            params.amount,
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts.bet.authority = ctx.accounts.user.key();
+        ctx.accounts.bet.user = ctx.accounts.user.key().to_bytes();
+        ctx.accounts.bet.bump = ctx.bumps.bet;
+
+        // Once stakes are held in tokens a bet only counts if its stake reaches the pool vault
+        if ctx.accounts.store.stake_mint == Pubkey::default() {
+            return Ok(());
+        }
+        let accounts = &ctx.accounts;
+        let (Some(pool_vault), Some(user_token), Some(token_program)) =
+            (&accounts.pool_vault, &accounts.user_token, &accounts.token_program)
+        else {
+            return err!(MyOAppError::MissingStakeAccounts);
+        };
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Transfer {
+                    from: user_token.to_account_info(),
+                    to: pool_vault.to_account_info(),
+                    authority: accounts.user.to_account_info(),
+                },
+            ),
+            params.amount,
+        )
+    }
+}
+
+// Shared by place_bet and the cross-chain PlaceBet paths (lz_receive and lz_compose).
+// Callers set who owns the bet (authority or remote user) themselves.
+pub fn record_bet(
+    pool: &mut BetPool,
+    pool_key: Pubkey,
+    bet: &mut Bet,
+    option: u8,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    // Enforce betting window
+    pool.sync_status(now);
+    require!(pool.is_accepting_bets(now), PlaceBetError::BettingClosed);
+    // Enforce valid option (check against option_amounts length)
+    require!((option as usize) < pool.option_amounts.len(), PlaceBetError::InvalidOption);
+    require!(amount > 0, PlaceBetError::ZeroAmount);
+    // One bet per user and pool. The bet account is init_if_needed, so an existing bet reaches here
+    // with its pool already set.
+    require!(bet.pool == Pubkey::default(), PlaceBetError::DuplicateBet);
+    // Update pool amounts
+    pool.pool_amount = pool.pool_amount.checked_add(amount).ok_or(PlaceBetError::PoolTotalsOverflow)?;
+    let option_amount = &mut pool.option_amounts[option as usize];
+    *option_amount = option_amount.checked_add(amount).ok_or(PlaceBetError::PoolTotalsOverflow)?;
+    // Update unique bettors
+    pool.unique_bettors = pool.unique_bettors.checked_add(1).ok_or(PlaceBetError::PoolTotalsOverflow)?;
+    // Set bet fields
+    bet.pool = pool_key;
+    bet.option = option;
+    bet.amount = amount;
+    bet.claimed = false;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::bet_pool::PoolStatus;
+    use crate::test_utils::*;
 
-        // Enforce time window
-        require!(clock.unix_timestamp >= pool.start_time && clock.unix_timestamp < pool.lock_time, PlaceBetError::BettingClosed);
-        // Enforce valid option (check against option_amounts length)
-        require!((params.option as usize) < pool.option_amounts.len(), PlaceBetError::InvalidOption);
-        // Prevent duplicate bets (if bet already exists, Anchor will error on init)
-        // Update pool amounts
-        pool.pool_amount = pool.pool_amount.checked_add(params.amount).unwrap();
-        pool.option_amounts[params.option as usize] = pool.option_amounts[params.option as usize].checked_add(params.amount).unwrap();
-        // Update unique bettors
-        pool.unique_bettors = pool.unique_bettors.checked_add(1).unwrap();
-        // Set bet fields
-        bet.authority = ctx.accounts.user.key();
-        bet.pool = ctx.accounts.bet_pool.key();
-        bet.option = params.option;
-        bet.amount = params.amount;
-        bet.claimed = false;
-        Ok(())
+    const BUMP: u8 = 254;
+
+    // `user` bets `amount` on `option` of an open pool with 100 staked on each of its two options,
+    // returning the pool and the bet
+    fn place(user: Pubkey, option: u8, amount: u64) -> Result<(BetPool, Bet)> {
+        let pool_key = Pubkey::new_unique();
+        let new_bet = Bet { option: 0, amount: 0, ..bet(Pubkey::default(), Pubkey::default()) };
+        let mut accounts = PlaceBet {
+            bet_pool: load(program_account(pool_key, &bet_pool(PoolStatus::Open), 8 + BetPool::INIT_SPACE)),
+            bet: load(program_account(Pubkey::new_unique(), &new_bet, 8 + Bet::INIT_SPACE)),
+            user: signer(user),
+            store: store_account(&store()),
+            pool_vault: None,
+            user_token: None,
+            token_program: None,
+            system_program: system_program(),
+        };
+        let bumps = PlaceBetBumps { bet: BUMP, ..Default::default() };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], bumps);
+        PlaceBet::apply(&mut ctx, &PlaceBetParams { user, option, amount })?;
+        Ok(((*accounts.bet_pool).clone(), (*accounts.bet).clone()))
+    }
+
+    #[test]
+    fn bets_are_recorded_for_the_signer() {
+        set_now(0);
+        let user = Pubkey::new_unique();
+        let (pool, bet) = place(user, 0, 50).unwrap();
+        assert_eq!((pool.pool_amount, pool.option_amounts, pool.unique_bettors), (250, vec![150, 100], 3));
+        assert_eq!((bet.option, bet.amount, bet.claimed), (0, 50, false));
+        assert_eq!((bet.authority, bet.user, bet.origin_eid), (user, user.to_bytes(), 0));
+        assert_eq!(bet.bump, BUMP);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn bets_must_name_an_option_of_a_pool_taking_bets() {
+        set_now(0);
+        let err = place(Pubkey::new_unique(), 2, 50).unwrap_err();
+        assert_eq!(err, PlaceBetError::InvalidOption.into());
+
+        set_now(1);
+        let err = place(Pubkey::new_unique(), 0, 50).unwrap_err();
+        assert_eq!(err, PlaceBetError::BettingClosed.into());
+    }
+}
@@ -25,8 +25,10 @@ pub struct QuoteSend<'info> {
 
 impl<'info> QuoteSend<'info> {
     pub fn apply(ctx: &Context<QuoteSend>, params: &QuoteSendParams) -> Result<MessagingFee> {
+        check_outbound(&params.message)?;
         // Encode the payload for quoting
-        let message = msg_codec::encode(&params.message);
+        let clock = Clock::get()?;
+        let message = msg_codec::encode_betlify_message(params.request_id, clock.unix_timestamp as u64, &params.message);
 
         // Ask the Endpoint how much a send would cost
         let quote_params = QuoteParams {
@@ -49,7 +51,8 @@ impl<'info> QuoteSend<'info> {
 pub struct QuoteSendParams {
     pub dst_eid: u32,
     pub receiver: [u8; 32],
-    pub message: String,
+    pub request_id: u64,
+    pub message: msg_codec::BetlifyMessage,
     pub options: Vec<u8>,
     pub pay_in_lz_token: bool,
 }
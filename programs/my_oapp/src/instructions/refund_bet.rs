@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use crate::instructions::claim_winnings::pay_from_pool_vault;
+use crate::state::bet_pool::{BetPool, PoolStatus};
+use crate::state::bet::Bet;
+use crate::state::IdentityLink;
+use crate::state::store::Store;
+use crate::errors::MyOAppError;
+use crate::{POOL_VAULT_SEED, STORE_SEED};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RefundBetParams {
+    // No parameters needed for this instruction
+}
+
+#[derive(Accounts)]
+pub struct RefundBet<'info> {
+    #[account(mut)]
+    pub bet_pool: Account<'info, BetPool>,
+    #[account(mut)]
+    pub bet: Account<'info, Bet>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    /// Link of the bet's remote user, when a linked Solana key manages a cross-chain bet
+    pub identity_link: Option<Account<'info, IdentityLink>>,
+    /// Vault holding the pool's stakes, required once stakes are held in tokens
+    #[account(mut, seeds = [POOL_VAULT_SEED, bet_pool.key().as_ref()], bump)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+    /// Stake token account the stake is returned to
+    #[account(mut)]
+    pub user_token: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code(offset = 6700)]
+pub enum RefundBetError {
+    #[msg("Pool is not voided")] 
+    PoolNotVoided,
+    #[msg("Already refunded")] 
+    AlreadyRefunded,
+    #[msg("Invalid bet for this pool")] 
+    InvalidBet,
+    #[msg("Bet does not belong to this user")] 
+    InvalidAuthority,
+    #[msg("Stake is held on the bet's source chain, refund it from there")] 
+    RefundOnSourceChain,
+}
+
+impl<'info> RefundBet<'info> {
+    pub fn apply(ctx: &mut Context<Self>, _params: &RefundBetParams) -> Result<()> {
+        require!(ctx.accounts.store.refunds_open(), MyOAppError::Paused);
+        let identity_link = ctx.accounts.identity_link.as_deref();
+        require!(
+            ctx.accounts.bet.is_managed_by(ctx.accounts.user.key(), identity_link),
+            RefundBetError::InvalidAuthority
+        );
+        // Cross-chain stakes only reach Solana in tokens, otherwise the source chain returns them
+        require!(
+            ctx.accounts.bet.origin_eid == 0 || ctx.accounts.store.stake_mint != Pubkey::default(),
+            RefundBetError::RefundOnSourceChain
+        );
+        let pool_key = ctx.accounts.bet_pool.key();
+        let stake = settle_refund(&ctx.accounts.bet_pool, pool_key, &mut ctx.accounts.bet)?;
+        pay_from_pool_vault(
+            &ctx.accounts.store,
+            ctx.accounts.pool_vault.as_ref(),
+            ctx.accounts.user_token.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            stake,
+        )
+    }
+}
+
+// Shared by refund_bet and the ClaimWinnings branch of lz_receive, which refunds bets on voided pools.
+// Marks the bet as refunded and returns the stake owed back to the bettor.
+pub fn settle_refund(pool: &BetPool, pool_key: Pubkey, bet: &mut Bet) -> Result<u64> {
+    require!(pool.status == PoolStatus::Voided, RefundBetError::PoolNotVoided);
+    require_keys_eq!(bet.pool, pool_key, RefundBetError::InvalidBet);
+    require!(!bet.claimed, RefundBetError::AlreadyRefunded);
+
+    bet.claimed = true;
+    Ok(bet.amount)
+}
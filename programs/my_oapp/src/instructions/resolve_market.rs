@@ -1,20 +1,23 @@
 use anchor_lang::prelude::*;
-use crate::state::bet_pool::{BetPool, PoolStatus};
+use crate::state::bet_pool::{BetPool, PoolStatus, MAX_EVIDENCE_URI_LEN};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct ResolveMarketParams {
     pub winning_option: u8,
+    pub evidence_uri: String,
+    pub evidence_hash: [u8; 32],
 }
 
 #[derive(Accounts)]
 #[instruction(params: ResolveMarketParams)]
 pub struct ResolveMarket<'info> {
-    #[account(mut)]
+    // Cross-chain pools have no Solana creator and are resolved by a ResolveMarket message
+    #[account(mut, has_one = creator)]
     pub bet_pool: Account<'info, BetPool>,
     pub creator: Signer<'info>,
 }
 
-#[error_code]
+#[error_code(offset = 6500)]
 pub enum ResolveMarketError {
     #[msg("Market not ended")] 
     MarketNotEnded,
@@ -22,22 +25,53 @@ pub enum ResolveMarketError {
     ResultAlreadySet,
     #[msg("Invalid option")] 
     InvalidOption,
+    #[msg("Resolution deadline has passed")] 
+    ResolutionDeadlinePassed,
+    #[msg("Pool has been voided")] 
+    PoolVoided,
+    #[msg("Evidence URI too long")] 
+    EvidenceUriTooLong,
+    #[msg("Betting is still open")] 
+    BettingNotClosed,
 }
 
 impl<'info> ResolveMarket<'info> {
     pub fn apply(ctx: &mut Context<Self>, params: &ResolveMarketParams) -> Result<()> {
-        let pool = &mut ctx.accounts.bet_pool;
         let clock = Clock::get()?;
+        resolve_pool(
+            &mut ctx.accounts.bet_pool,
+            params.winning_option,
+            &params.evidence_uri,
+            params.evidence_hash,
+            clock.unix_timestamp,
+        )
+    }
+}
 
-        // Only allow resolution after end time
-        require!(clock.unix_timestamp >= pool.end_time, ResolveMarketError::MarketNotEnded);
-        require!(!pool.is_result_set, ResolveMarketError::ResultAlreadySet);
-        require!((params.winning_option as usize) < pool.option_amounts.len(), ResolveMarketError::InvalidOption);
+// Shared by resolve_market and the ResolveMarket branch of lz_receive
+pub fn resolve_pool(
+    pool: &mut BetPool,
+    winning_option: u8,
+    evidence_uri: &str,
+    evidence_hash: [u8; 32],
+    now: i64,
+) -> Result<()> {
+    pool.sync_status(now);
 
-        pool.status = PoolStatus::Resolved;
-        pool.winning_option = params.winning_option;
-        pool.is_result_set = true;
-        pool.result = params.winning_option;
-        Ok(())
-    }
-} 
\ No newline at end of file
+    // Only allow resolution after end time
+    require!(now >= pool.end_time, ResolveMarketError::MarketNotEnded);
+    require!(now < pool.resolution_deadline, ResolveMarketError::ResolutionDeadlinePassed);
+    require!(pool.status != PoolStatus::Voided, ResolveMarketError::PoolVoided);
+    require!(!pool.is_result_set, ResolveMarketError::ResultAlreadySet);
+    require!(pool.status == PoolStatus::Closed, ResolveMarketError::BettingNotClosed);
+    require!((winning_option as usize) < pool.option_amounts.len(), ResolveMarketError::InvalidOption);
+    require!(evidence_uri.len() <= MAX_EVIDENCE_URI_LEN, ResolveMarketError::EvidenceUriTooLong);
+
+    pool.status = PoolStatus::Resolved;
+    pool.winning_option = winning_option;
+    pool.is_result_set = true;
+    pool.result = winning_option;
+    pool.evidence_uri = evidence_uri.to_string();
+    pool.evidence_hash = evidence_hash;
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use crate::*;
+
+// Executes a message that failed in a non-blocking lz_receive. The message was already cleared on
+// the Endpoint, so the FailedMessage PDA is the only record of it and is closed once it succeeds.
+// `remaining_accounts` are the message's accounts, as lz_receive takes them, followed by the
+// Endpoint::send accounts for payouts and the ack, or the OFT send accounts for payouts in tokens.
+
+#[derive(Accounts)]
+#[instruction(params: RetryFailedMessageParams)]
+pub struct RetryFailedMessage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [FAILED_MESSAGE_SEED, &store.key().to_bytes(), &params.guid],
+        bump = failed_message.bump
+    )]
+    pub failed_message: Account<'info, FailedMessage>,
+
+    #[account(
+        seeds = [PEER_SEED, &store.key().to_bytes(), &failed_message.src_eid.to_be_bytes()],
+        bump = peer.bump,
+        constraint = failed_message.sender == peer.peer_address
+    )]
+    pub peer: Account<'info, PeerConfig>,
+
+    /// Reimburses the payer for the ack, like in lz_receive
+    #[account(mut, seeds = [FEE_VAULT_SEED, &store.key().to_bytes()], bump)]
+    pub fee_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RetryFailedMessage<'info> {
+    pub fn apply(ctx: &mut Context<'_, '_, 'info, 'info, Self>, _params: &RetryFailedMessageParams) -> Result<()> {
+        let src_eid = ctx.accounts.failed_message.src_eid;
+        let envelope = msg_codec::decode_betlify_envelope(&ctx.accounts.failed_message.message)?;
+        let actions = envelope.message.actions();
+
+        // Rate limits were already charged when the message was received
+        check_inbound(&ctx.accounts.store, &ctx.accounts.peer, src_eid, actions)?;
+
+        let store_key = ctx.accounts.store.key();
+        // Pools the message creates get their ids now, not when it first failed
+        let first_pool_id = ctx.accounts.store.pools_count;
+        let required = required_accounts(actions, first_pool_id);
+        verify_message_accounts(ctx.program_id, store_key, src_eid, &required, ctx.remaining_accounts)?;
+        let (message_infos, send_accounts) = ctx.remaining_accounts.split_at(required.len());
+
+        let mut accounts = load_message_accounts(
+            ctx.program_id,
+            store_key,
+            src_eid,
+            &required,
+            message_infos,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        let payouts = execute_actions(&mut ctx.accounts.store, &mut accounts, src_eid, actions)?;
+        accounts.exit(ctx.program_id)?;
+        let ack = acknowledges(&ctx.accounts.peer, &ctx.accounts.store, &payouts);
+        for payout in payouts {
+            issue_payout(
+                ctx.program_id,
+                &ctx.accounts.store,
+                &ctx.accounts.peer,
+                send_accounts,
+                src_eid,
+                envelope.header.request_id,
+                payout,
+            )?;
+        }
+        // The source chain was told the message failed, so it is told once it succeeds too
+        if ack {
+            let (pool_id, bet) = ack_target(ctx.program_id, store_key, src_eid, first_pool_id, actions);
+            let ack_accounts = AckAccounts {
+                store: &ctx.accounts.store,
+                peer: &ctx.accounts.peer,
+                payer: ctx.accounts.payer.as_ref(),
+                fee_vault: ctx.accounts.fee_vault.as_ref(),
+                fee_vault_bump: ctx.bumps.fee_vault,
+                system_program: ctx.accounts.system_program.as_ref(),
+                send_accounts,
+            };
+            let guid = ctx.accounts.failed_message.guid;
+            send_ack(&ack_accounts, src_eid, guid, envelope.header.request_id, msg_codec::ACK_EXECUTED, pool_id, bet)?;
+        }
+
+        msg!("Retried message {:?} from eid {}", ctx.accounts.failed_message.guid, src_eid);
+        Ok(())
+    }
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct RetryFailedMessageParams {
+    pub guid: [u8; 32],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::MyOAppError;
+    use crate::state::BetPool;
+    use crate::test_utils::*;
+
+    const GUID: [u8; 32] = [5; 32];
+
+    // The accounts of `message` as a failed lz_receive leaves them: created, but holding only their
+    // discriminator
+    fn left_by_failed_receive(message: &msg_codec::BetlifyMessage, pools_count: u64) -> &'static [AccountInfo<'static>] {
+        let infos: Vec<_> = required_accounts(message.actions(), pools_count)
+            .iter()
+            .map(|required| {
+                let (address, _) = required.account.address(&crate::ID, store_key(), SRC_EID);
+                let mut data = vec![0; required.account.space()];
+                data[..8].copy_from_slice(&required.account.discriminator());
+                account_info(address, crate::ID, 1_000_000, data)
+            })
+            .collect();
+        Box::leak(infos.into_boxed_slice())
+    }
+
+    // Retries `message`, returning the Store's pool count after it
+    fn retry(
+        store: Store,
+        peer_config: PeerConfig,
+        message: &msg_codec::BetlifyMessage,
+        remaining_accounts: &'static [AccountInfo<'static>],
+    ) -> Result<u64> {
+        set_now(NOW);
+        let message = msg_codec::encode_betlify_message(1, NOW as u64, message);
+        let failed_message = FailedMessage {
+            src_eid: SRC_EID,
+            sender: SENDER,
+            nonce: 1,
+            guid: GUID,
+            error_code: MyOAppError::Paused.into(),
+            bump: 0,
+            message,
+        };
+        let space = FailedMessage::space(failed_message.message.len());
+        // Empty, so acks are skipped
+        let fee_vault = account_info(Pubkey::new_unique(), anchor_lang::system_program::ID, 0, Vec::new());
+        let mut accounts = RetryFailedMessage {
+            payer: signer(Pubkey::new_unique()),
+            store: store_account(&store),
+            failed_message: load(program_account(Pubkey::new_unique(), &failed_message, space)),
+            peer: load(program_account(Pubkey::new_unique(), &peer_config, 8 + PeerConfig::INIT_SPACE)),
+            fee_vault: SystemAccount::try_from(&*Box::leak(Box::new(fee_vault))).unwrap(),
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, remaining_accounts, Default::default());
+        RetryFailedMessage::apply(&mut ctx, &RetryFailedMessageParams { guid: GUID })?;
+        Ok(accounts.store.pools_count)
+    }
+
+    #[test]
+    fn retries_execute_the_message_and_fill_in_the_bumps() {
+        let message = create_pool(evm_user(9), 1);
+        let infos = left_by_failed_receive(&message, 2);
+        let pools_count = retry(Store { pools_count: 2, ..store() }, peer(SENDER), &message, infos).unwrap();
+        assert_eq!(pools_count, 3);
+
+        let pool: BetPool = read(&infos[0]);
+        let (_, bump) = pda::bet_pool(&store_key(), 2).address(&crate::ID);
+        assert_eq!((pool.id, pool.bump), (2, bump));
+        assert_eq!(pool.remote_creator, msg_codec::remote_user([9; 20]));
+    }
+
+    #[test]
+    fn acks_the_fee_vault_cannot_pay_for_dont_fail_a_retry() {
+        let message = create_pool(evm_user(9), 1);
+        let infos = left_by_failed_receive(&message, 0);
+        let peer_config = PeerConfig { ack_mode: AckMode::FeeVault, response_fee: 5_000, ..peer(SENDER) };
+        take_invoked();
+        // The fee vault can't pay for the ack, which is skipped without failing the retry
+        assert_eq!(retry(store(), peer_config, &message, infos).unwrap(), 1);
+        assert!(take_invoked().is_empty());
+    }
+
+    #[test]
+    fn retries_fail_while_the_message_still_cannot_execute() {
+        let message = create_pool(evm_user(9), 1);
+        let infos = left_by_failed_receive(&message, 0);
+        let err = retry(Store { paused: true, ..store() }, peer(SENDER), &message, infos).unwrap_err();
+        assert_eq!(err, MyOAppError::Paused.into());
+        let err = retry(store(), PeerConfig { paused: true, ..peer(SENDER) }, &message, infos).unwrap_err();
+        assert_eq!(err, MyOAppError::PeerPaused.into());
+
+        // Nothing is written to the accounts until the message succeeds
+        let pool: BetPool = read(&infos[0]);
+        assert_eq!(pool.bump, 0);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::*;
 use anchor_lang::prelude::*;
+use crate::errors::MyOAppError;
 use oapp::endpoint::{
     instructions::SendParams, state::EndpointSettings, ENDPOINT_SEED, ID as ENDPOINT_ID,
 };
@@ -7,6 +8,9 @@ use oapp::endpoint::{
 #[derive(Accounts)]
 #[instruction(params: SendMessageParams)]
 pub struct Send<'info> {
+    #[account(address = store.admin)]
+    /// Admin of the OApp store. Messages go out in the name of the Store, so only it may send them.
+    pub admin: Signer<'info>,
     #[account(
         seeds = [
             PEER_SEED,
@@ -26,8 +30,12 @@ pub struct Send<'info> {
 }
 impl<'info> Send<'info> {
     pub fn apply(ctx: &mut Context<Send>, params: &SendMessageParams) -> Result<()> {
+        require!(!ctx.accounts.store.paused, MyOAppError::Paused);
+        require!(!ctx.accounts.peer.paused, MyOAppError::PeerPaused);
+        check_outbound(&params.message)?;
         // Serialize the message according to our codec
-        let message = msg_codec::encode(&params.message);
+        let clock = Clock::get()?;
+        let message = msg_codec::encode_betlify_message(params.request_id, clock.unix_timestamp as u64, &params.message);
         // Prepare the seeds for the OApp Store PDA, which is used to sign the CPI call to the Endpoint program.
         let seeds: &[&[u8]] = &[STORE_SEED, &[ctx.accounts.store.bump]];
 
@@ -56,11 +64,75 @@ impl<'info> Send<'info> {
     }
 }
 
+// Checks shared by send and quote_send, so a message is only quoted if it could be sent
+pub fn check_outbound(message: &msg_codec::BetlifyMessage) -> Result<()> {
+    if let msg_codec::BetlifyMessage::Batch(items) = message {
+        require!(
+            (1..=msg_codec::MAX_BATCH_LEN).contains(&items.0.len()),
+            msg_codec::MsgCodecError::InvalidBatchLength
+        );
+    }
+    for action in message.actions() {
+        require!(
+            !matches!(action, msg_codec::BetlifyMessage::Batch(_)),
+            msg_codec::MsgCodecError::NestedBatch
+        );
+        // Payouts, failure notices and acks are only ever issued by the program itself
+        require!(
+            !matches!(
+                action,
+                msg_codec::BetlifyMessage::PayoutIssued { .. }
+                    | msg_codec::BetlifyMessage::MessageFailed { .. }
+                    | msg_codec::BetlifyMessage::ActionAck { .. }
+            ),
+            SendError::ProgramIssuedMessage
+        );
+    }
+    Ok(())
+}
+
 #[derive(Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct SendMessageParams {
     pub dst_eid: u32,
-    pub message: String,
+    pub request_id: u64, // Echoed back by the destination in any response
+    pub message: msg_codec::BetlifyMessage,
     pub options: Vec<u8>,
     pub native_fee: u64,
     pub lz_token_fee: u64,
 }
+
+#[error_code(offset = 7300)]
+pub enum SendError {
+    #[msg("Message type can only be sent by the program")]
+    ProgramIssuedMessage,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim() -> msg_codec::BetlifyMessage {
+        msg_codec::BetlifyMessage::ClaimWinnings { authority: [1; 20], pool_id: 0 }
+    }
+
+    fn batch(items: Vec<msg_codec::BetlifyMessage>) -> msg_codec::BetlifyMessage {
+        msg_codec::BetlifyMessage::Batch(msg_codec::BatchItems(items))
+    }
+
+    #[test]
+    fn only_messages_the_codec_and_the_destination_accept_go_out() {
+        check_outbound(&claim()).unwrap();
+        check_outbound(&batch(vec![claim(), claim()])).unwrap();
+
+        let err = check_outbound(&batch(vec![])).unwrap_err();
+        assert_eq!(err, msg_codec::MsgCodecError::InvalidBatchLength.into());
+        let err = check_outbound(&batch(vec![claim(); msg_codec::MAX_BATCH_LEN + 1])).unwrap_err();
+        assert_eq!(err, msg_codec::MsgCodecError::InvalidBatchLength.into());
+        let err = check_outbound(&batch(vec![batch(vec![claim()])])).unwrap_err();
+        assert_eq!(err, msg_codec::MsgCodecError::NestedBatch.into());
+
+        let ack = msg_codec::BetlifyMessage::ActionAck { guid: [0; 32], status: 0, pool_id: 0, bet: [0; 32] };
+        assert_eq!(check_outbound(&ack).unwrap_err(), SendError::ProgramIssuedMessage.into());
+        assert_eq!(check_outbound(&batch(vec![claim(), ack])).unwrap_err(), SendError::ProgramIssuedMessage.into());
+    }
+}
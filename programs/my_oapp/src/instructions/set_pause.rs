@@ -0,0 +1,213 @@
+use crate::*;
+use anchor_lang::prelude::*;
+
+// Circuit breaker. The pauser can only make things stricter (pause, or stop claims/refunds during a
+// pause); lifting a pause or re-enabling claims/refunds is left to the admin.
+
+#[derive(Accounts)]
+#[instruction(params: SetPauseParams)]
+pub struct SetPause<'info> {
+    #[account(
+        constraint = authority.key() == store.admin || authority.key() == store.pauser @ SetPauseError::Unauthorized
+    )]
+    /// Admin or pauser of the OApp store
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
+    /// Store PDA of this OApp
+    pub store: Account<'info, Store>,
+    #[account(mut, seeds = [PEER_SEED, &store.key().to_bytes(), &params.param.remote_eid().to_be_bytes()], bump = peer.bump)]
+    /// Only needed to pause a single peer
+    pub peer: Option<Account<'info, PeerConfig>>,
+}
+
+#[error_code(offset = 7600)]
+pub enum SetPauseError {
+    #[msg("Signer is neither the admin nor the pauser")]
+    Unauthorized,
+    #[msg("Only the admin can lift a pause")]
+    AdminOnly,
+    #[msg("Peer account required")]
+    MissingPeer,
+}
+
+impl SetPause<'_> {
+    pub fn apply(ctx: &mut Context<SetPause>, params: &SetPauseParams) -> Result<()> {
+        if !params.param.tightens() {
+            require_keys_eq!(ctx.accounts.authority.key(), ctx.accounts.store.admin, SetPauseError::AdminOnly);
+        }
+        match params.param {
+            PauseParam::Paused(paused) => {
+                ctx.accounts.store.paused = paused;
+            },
+            PauseParam::PeerPaused { paused, .. } => {
+                let peer = ctx.accounts.peer.as_mut().ok_or(SetPauseError::MissingPeer)?;
+                peer.paused = paused;
+            },
+            PauseParam::ClaimsWhilePaused(enabled) => {
+                ctx.accounts.store.claims_while_paused = enabled;
+            },
+            PauseParam::RefundsWhilePaused(enabled) => {
+                ctx.accounts.store.refunds_while_paused = enabled;
+            },
+        }
+        msg!("Pause setting changed by {}", ctx.accounts.authority.key());
+        Ok(())
+    }
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct SetPauseParams {
+    pub param: PauseParam,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub enum PauseParam {
+    /// Freezes pools, bets, claims, refunds and messaging everywhere
+    Paused(bool),
+    /// Freezes messaging with one peer
+    PeerPaused { remote_eid: u32, paused: bool },
+    /// Lets claims through while the Store is paused
+    ClaimsWhilePaused(bool),
+    /// Lets refunds through while the Store is paused
+    RefundsWhilePaused(bool),
+}
+
+impl PauseParam {
+    // Remote eid of the peer being paused, used to check the optional peer account
+    pub fn remote_eid(&self) -> u32 {
+        match *self {
+            PauseParam::PeerPaused { remote_eid, .. } => remote_eid,
+            _ => 0,
+        }
+    }
+
+    fn tightens(&self) -> bool {
+        match *self {
+            PauseParam::Paused(paused) | PauseParam::PeerPaused { paused, .. } => paused,
+            PauseParam::ClaimsWhilePaused(enabled) | PauseParam::RefundsWhilePaused(enabled) => !enabled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::claim_winnings::{ClaimWinnings, ClaimWinningsParams};
+    use crate::instructions::refund_bet::{RefundBet, RefundBetParams};
+    use crate::errors::MyOAppError;
+    use crate::state::{bet::Bet, BetPool, PoolStatus};
+    use crate::test_utils::*;
+
+    const BETTOR: Pubkey = Pubkey::new_from_array([3; 32]);
+
+    fn admin() -> Pubkey {
+        store().admin
+    }
+
+    fn pauser() -> Pubkey {
+        store().pauser
+    }
+
+    fn set_pause(store: &mut Account<'static, Store>, authority: Pubkey, param: PauseParam) -> Result<()> {
+        let mut accounts = SetPause { authority: signer(authority), store: store.clone(), peer: None };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        SetPause::apply(&mut ctx, &SetPauseParams { param })?;
+        store.set_inner((*accounts.store).clone());
+        Ok(())
+    }
+
+    // A pool with 100 staked on each of its two options, and BETTOR's bet on option 1
+    fn pool_and_bet(status: PoolStatus) -> (Account<'static, BetPool>, Account<'static, Bet>) {
+        let pool_key = Pubkey::new_unique();
+        (
+            load(program_account(pool_key, &bet_pool(status), 8 + BetPool::INIT_SPACE)),
+            load(program_account(Pubkey::new_unique(), &bet(BETTOR, pool_key), 8 + Bet::INIT_SPACE)),
+        )
+    }
+
+    fn claim(store: &Account<'static, Store>) -> Result<()> {
+        let (bet_pool, bet) = pool_and_bet(PoolStatus::Resolved);
+        let mut accounts = ClaimWinnings {
+            bet_pool,
+            bet,
+            user: signer(BETTOR),
+            store: store.clone(),
+            identity_link: None,
+            pool_vault: None,
+            user_token: None,
+            token_program: None,
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        ClaimWinnings::apply(&mut ctx, &ClaimWinningsParams {})
+    }
+
+    fn refund(store: &Account<'static, Store>) -> Result<()> {
+        let (bet_pool, bet) = pool_and_bet(PoolStatus::Voided);
+        let mut accounts = RefundBet {
+            bet_pool,
+            bet,
+            user: signer(BETTOR),
+            store: store.clone(),
+            identity_link: None,
+            pool_vault: None,
+            user_token: None,
+            token_program: None,
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        RefundBet::apply(&mut ctx, &RefundBetParams {})
+    }
+
+    #[test]
+    fn pauser_may_only_tighten() {
+        let mut store = store_account(&store());
+        set_pause(&mut store, pauser(), PauseParam::ClaimsWhilePaused(false)).unwrap();
+        set_pause(&mut store, pauser(), PauseParam::Paused(true)).unwrap();
+        assert!(store.paused);
+
+        for param in [
+            PauseParam::Paused(false),
+            PauseParam::ClaimsWhilePaused(true),
+            PauseParam::RefundsWhilePaused(true),
+            PauseParam::PeerPaused { remote_eid: 30101, paused: false },
+        ] {
+            let err = set_pause(&mut store, pauser(), param).unwrap_err();
+            assert_eq!(err, SetPauseError::AdminOnly.into());
+        }
+        assert!(store.paused && !store.claims_while_paused && !store.refunds_while_paused);
+    }
+
+    #[test]
+    fn admin_lifts_pauses() {
+        let mut store = store_account(&store());
+        set_pause(&mut store, pauser(), PauseParam::Paused(true)).unwrap();
+        set_pause(&mut store, admin(), PauseParam::ClaimsWhilePaused(true)).unwrap();
+        set_pause(&mut store, admin(), PauseParam::RefundsWhilePaused(true)).unwrap();
+        assert!(store.claims_while_paused && store.refunds_while_paused);
+        set_pause(&mut store, admin(), PauseParam::Paused(false)).unwrap();
+        assert!(!store.paused);
+
+        // Pausing a single peer needs its account
+        let param = PauseParam::PeerPaused { remote_eid: 30101, paused: true };
+        assert_eq!(set_pause(&mut store, admin(), param).unwrap_err(), SetPauseError::MissingPeer.into());
+    }
+
+    #[test]
+    fn claims_and_refunds_follow_the_pause() {
+        let mut store = store_account(&store());
+        claim(&store).unwrap();
+        refund(&store).unwrap();
+
+        set_pause(&mut store, pauser(), PauseParam::Paused(true)).unwrap();
+        assert_eq!(claim(&store).unwrap_err(), MyOAppError::Paused.into());
+        assert_eq!(refund(&store).unwrap_err(), MyOAppError::Paused.into());
+
+        set_pause(&mut store, admin(), PauseParam::ClaimsWhilePaused(true)).unwrap();
+        claim(&store).unwrap();
+        assert_eq!(refund(&store).unwrap_err(), MyOAppError::Paused.into());
+
+        set_pause(&mut store, admin(), PauseParam::RefundsWhilePaused(true)).unwrap();
+        refund(&store).unwrap();
+    }
+}
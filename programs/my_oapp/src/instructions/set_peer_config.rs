@@ -14,12 +14,26 @@ pub struct SetPeerConfig<'info> {
     #[account(
         init_if_needed,
         payer = admin,
-        space = PeerConfig::SIZE,
+        space = 8 + PeerConfig::INIT_SPACE,
         seeds = [PEER_SEED, &store.key().to_bytes(), &params.remote_eid.to_be_bytes()],
         bump
     )]
     /// Peer configuration PDA for a specific remote chain
     pub peer: Account<'info, PeerConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Nonce::SIZE,
+        seeds = [
+            NONCE_SEED,
+            &store.key().to_bytes(),
+            &params.remote_eid.to_be_bytes(),
+            &params.peer_address(&peer)
+        ],
+        bump
+    )]
+    /// Inbound nonce PDA for the (possibly new) peer address
+    pub nonce_account: Account<'info, Nonce>,
     #[account(seeds = [STORE_SEED], bump = store.bump)]
     /// Store PDA of this OApp
     pub store: Account<'info, Store>,
@@ -28,6 +42,11 @@ pub struct SetPeerConfig<'info> {
 
 impl SetPeerConfig<'_> {
     pub fn apply(ctx: &mut Context<SetPeerConfig>, params: &SetPeerConfigParams) -> Result<()> {
+        // A new peer accepts every message type until the admin restricts it
+        if !ctx.accounts.peer.initialized {
+            ctx.accounts.peer.allowed_msg_types = ALL_MSG_TYPES;
+            ctx.accounts.peer.initialized = true;
+        }
         // Update or create the peer config PDA
         match params.config.clone() {
             PeerConfigParam::PeerAddress(peer_address) => {
@@ -39,9 +58,32 @@ impl SetPeerConfig<'_> {
                 oapp::options::assert_type_3(&send_and_call)?;
                 ctx.accounts.peer.enforced_options.send_and_call = send_and_call;
             },
+            PeerConfigParam::ResponseFee(response_fee) => {
+                ctx.accounts.peer.response_fee = response_fee;
+            },
+            PeerConfigParam::OrderedNonce(ordered_nonce) => {
+                ctx.accounts.peer.ordered_nonce = ordered_nonce;
+            },
+            PeerConfigParam::AllowedMsgTypes(allowed_msg_types) => {
+                ctx.accounts.peer.allowed_msg_types = allowed_msg_types;
+            },
+            PeerConfigParam::NonBlocking { enabled, notify_source } => {
+                ctx.accounts.peer.non_blocking = enabled;
+                ctx.accounts.peer.notify_failures = notify_source;
+            },
+            PeerConfigParam::AckMode(ack_mode) => {
+                ctx.accounts.peer.ack_mode = ack_mode;
+            },
+            PeerConfigParam::RateLimits { window, max_messages, max_stake } => {
+                require!(window > 0, SetPeerConfigError::InvalidRateLimitWindow);
+                let now = Clock::get()?.unix_timestamp;
+                ctx.accounts.peer.message_limit = RateLimit::new(max_messages, window, now);
+                ctx.accounts.peer.stake_limit = RateLimit::new(max_stake, window, now);
+            },
         }
-        // Store the PDA bump for later validation
+        // Store the PDA bumps for later validation
         ctx.accounts.peer.bump = ctx.bumps.peer;
+        ctx.accounts.nonce_account.bump = ctx.bumps.nonce_account;
         Ok(())
     }
 }
@@ -52,9 +94,70 @@ pub struct SetPeerConfigParams {
     pub config: PeerConfigParam,
 }
 
+impl SetPeerConfigParams {
+    // Peer address the config applies to once this update lands
+    pub fn peer_address(&self, peer: &PeerConfig) -> [u8; 32] {
+        match self.config {
+            PeerConfigParam::PeerAddress(peer_address) => peer_address,
+            _ => peer.peer_address,
+        }
+    }
+}
+
 #[derive(Clone, AnchorSerialize, AnchorDeserialize)]
 pub enum PeerConfigParam {
     PeerAddress([u8; 32]),
     /// Optionally enforce specific send options for this peer
     EnforcedOptions { send: Vec<u8>, send_and_call: Vec<u8> },
-}
\ No newline at end of file
+    /// Native fee the payer spends on responses (e.g. payouts) sent back to this peer
+    ResponseFee(u64),
+    /// Reject messages from this peer unless they arrive in nonce order
+    OrderedNonce(bool),
+    /// Bitmask of the msg_types this peer may send, bit n for msg_type n. New peers may send every
+    /// type, and 0 rejects them all.
+    AllowedMsgTypes(u16),
+    /// Store failed messages for retry instead of reverting, optionally telling the source chain
+    NonBlocking { enabled: bool, notify_source: bool },
+    /// Whether received messages are acknowledged back to the peer, and who pays for the acks
+    AckMode(AckMode),
+    /// Most inbound actions and PlaceBet stake accepted per `window` seconds, 0 for no limit
+    RateLimits { window: i64, max_messages: u64, max_stake: u64 },
+}
+
+#[error_code(offset = 7400)]
+pub enum SetPeerConfigError {
+    #[msg("Rate limit window must be positive")] InvalidRateLimitWindow,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // Applies `config` to `peer` as the admin, returning the updated peer. The bumps are left at 0.
+    fn set(peer: PeerConfig, config: PeerConfigParam) -> PeerConfig {
+        let nonce = Nonce { bump: 0, max_received_nonce: 0 };
+        let mut accounts = SetPeerConfig {
+            admin: signer(store().admin),
+            peer: load(program_account(Pubkey::new_unique(), &peer, 8 + PeerConfig::INIT_SPACE)),
+            nonce_account: load(program_account(Pubkey::new_unique(), &nonce, Nonce::SIZE)),
+            store: store_account(&store()),
+            system_program: system_program(),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        SetPeerConfig::apply(&mut ctx, &SetPeerConfigParams { remote_eid: 30101, config }).unwrap();
+        (*accounts.peer).clone()
+    }
+
+    #[test]
+    fn new_peers_accept_every_message_type_until_restricted() {
+        let new_peer = PeerConfig { allowed_msg_types: 0, initialized: false, ..peer([0; 32]) };
+        let configured = set(new_peer, PeerConfigParam::PeerAddress([1; 32]));
+        assert!(configured.initialized);
+        assert_eq!(configured.allowed_msg_types, ALL_MSG_TYPES);
+
+        // A peer closed to every message type stays closed through later updates
+        let closed = set(configured, PeerConfigParam::AllowedMsgTypes(0));
+        let updated = set(closed, PeerConfigParam::ResponseFee(5_000));
+        assert_eq!((updated.allowed_msg_types, updated.response_fee), (0, 5_000));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::*;
+use anchor_lang::system_program;
+
+// Registers the accounts lz_receive needs to respond to a remote chain, see ResponseAccounts.
+// Empty lists remove the route. The registry grows and shrinks with the routes it holds.
+
+#[derive(Accounts)]
+pub struct SetResponseAccounts<'info> {
+    #[account(mut, address = store.admin)]
+    /// Admin of the OApp store, pays for the registry growing
+    pub admin: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    /// Store PDA of this OApp
+    pub store: Account<'info, Store>,
+    #[account(mut, seeds = [RESPONSE_ACCOUNTS_SEED, &store.key().to_bytes()], bump = response_accounts.bump)]
+    pub response_accounts: Account<'info, ResponseAccounts>,
+    pub system_program: Program<'info, System>,
+}
+
+impl SetResponseAccounts<'_> {
+    pub fn apply(ctx: &mut Context<SetResponseAccounts>, params: &SetResponseAccountsParams) -> Result<()> {
+        let routes = &mut ctx.accounts.response_accounts.routes;
+        routes.retain(|route| route.eid != params.eid);
+        if !params.send.is_empty() || !params.oft_send.is_empty() {
+            routes.push(ResponseRoute {
+                eid: params.eid,
+                send: params.send.clone(),
+                oft_send: params.oft_send.clone(),
+            });
+        }
+
+        let space = ResponseAccounts::space(routes);
+        let info = ctx.accounts.response_accounts.to_account_info();
+        let rent = Rent::get()?.minimum_balance(space);
+        if rent > info.lamports() {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer { from: ctx.accounts.admin.to_account_info(), to: info.clone() },
+                ),
+                rent - info.lamports(),
+            )?;
+        } else {
+            let excess = info.lamports() - rent;
+            **info.try_borrow_mut_lamports()? -= excess;
+            **ctx.accounts.admin.try_borrow_mut_lamports()? += excess;
+        }
+        info.realloc(space, false)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct SetResponseAccountsParams {
+    pub eid: u32,
+    pub send: Vec<ResponseAccount>,
+    pub oft_send: Vec<ResponseAccount>,
+}
@@ -0,0 +1,118 @@
+use crate::*;
+use anchor_lang::prelude::*;
+
+// Admin-controlled settings that live on the Store PDA and apply to every pool
+
+#[derive(Accounts)]
+pub struct SetStoreConfig<'info> {
+    #[account(address = store.admin)]
+    /// Admin of the OApp store
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [STORE_SEED], bump = store.bump)]
+    /// Store PDA of this OApp
+    pub store: Account<'info, Store>,
+}
+
+#[error_code(offset = 7500)]
+pub enum SetStoreConfigError {
+    #[msg("Invalid pool durations")] 
+    InvalidPoolDurations,
+    #[msg("Invalid OFT decimal conversion rate")] 
+    InvalidDecimalRate,
+    #[msg("Keeper reward is more than the rent of a pool")] 
+    KeeperRewardTooHigh,
+    #[msg("Stake OFT can't change once pools exist")] 
+    StakeOftLocked,
+}
+
+impl SetStoreConfig<'_> {
+    pub fn apply(ctx: &mut Context<SetStoreConfig>, params: &SetStoreConfigParams) -> Result<()> {
+        match params.config.clone() {
+            StoreConfigParam::KeeperReward(keeper_reward) => {
+                // Paying more than a pool's rent would make creating and cranking pools profitable
+                let pool_rent = Rent::get()?.minimum_balance(8 + BetPool::INIT_SPACE);
+                require!(keeper_reward <= pool_rent, SetStoreConfigError::KeeperRewardTooHigh);
+                ctx.accounts.store.keeper_reward = keeper_reward;
+            },
+            StoreConfigParam::PoolDurations { min, max } => {
+                require!(min >= 0 && min <= max, SetStoreConfigError::InvalidPoolDurations);
+                ctx.accounts.store.min_pool_duration = min;
+                ctx.accounts.store.max_pool_duration = max;
+            },
+            StoreConfigParam::StakeOft { mint, oft_program, oft_store, ld2sd_rate } => {
+                require!(ld2sd_rate > 0, SetStoreConfigError::InvalidDecimalRate);
+                // Bets already placed were paid in (or never paid in) the current stake token, and
+                // claims pay out in whatever token is set when they settle
+                require!(ctx.accounts.store.pools_count == 0, SetStoreConfigError::StakeOftLocked);
+                ctx.accounts.store.stake_mint = mint;
+                ctx.accounts.store.oft_program = oft_program;
+                ctx.accounts.store.oft_store = oft_store;
+                ctx.accounts.store.stake_ld2sd_rate = ld2sd_rate;
+            },
+            StoreConfigParam::Pauser(pauser) => {
+                ctx.accounts.store.pauser = pauser;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct SetStoreConfigParams {
+    pub config: StoreConfigParam,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub enum StoreConfigParam {
+    /// Lamports paid to the keeper that closes betting on a pool with bets, at most a pool's rent
+    KeeperReward(u64),
+    /// Bounds on a pool's start_time..end_time span, in seconds
+    PoolDurations { min: i64, max: i64 },
+    /// OFT used to move cross-chain stakes in and payouts out, with its ld2sd_rate
+    /// (10^(local decimals - shared decimals)). Only settable before the first pool is created.
+    StakeOft { mint: Pubkey, oft_program: Pubkey, oft_store: Pubkey, ld2sd_rate: u64 },
+    /// Account allowed to pause alongside the admin
+    Pauser(Pubkey),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn the_stake_oft_is_fixed_once_pools_exist() {
+        let set_stake_oft = |store_state: Store| {
+            let mut accounts = SetStoreConfig { admin: signer(store_state.admin), store: store_account(&store_state) };
+            let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+            let config = StoreConfigParam::StakeOft {
+                mint: Pubkey::new_unique(),
+                oft_program: Pubkey::new_unique(),
+                oft_store: Pubkey::new_unique(),
+                ld2sd_rate: 1_000,
+            };
+            SetStoreConfig::apply(&mut ctx, &SetStoreConfigParams { config })?;
+            Ok::<_, Error>((*accounts.store).clone())
+        };
+        let configured = set_stake_oft(store()).unwrap();
+        assert_ne!(configured.stake_mint, Pubkey::default());
+        assert_eq!(configured.stake_ld2sd_rate, 1_000);
+
+        let err = set_stake_oft(Store { pools_count: 1, ..store() }).unwrap_err();
+        assert_eq!(err, SetStoreConfigError::StakeOftLocked.into());
+    }
+
+    #[test]
+    fn keeper_rewards_are_capped_at_the_rent_of_a_pool() {
+        set_now(0);
+        let set_reward = |keeper_reward| {
+            let mut accounts = SetStoreConfig { admin: signer(store().admin), store: store_account(&store()) };
+            let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+            let config = StoreConfigParam::KeeperReward(keeper_reward);
+            SetStoreConfig::apply(&mut ctx, &SetStoreConfigParams { config })
+        };
+        let pool_rent = Rent::default().minimum_balance(8 + BetPool::INIT_SPACE);
+        set_reward(pool_rent).unwrap();
+        assert_eq!(set_reward(pool_rent + 1).unwrap_err(), SetStoreConfigError::KeeperRewardTooHigh.into());
+    }
+}
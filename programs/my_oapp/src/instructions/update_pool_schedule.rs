@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use crate::state::bet_pool::{BetPool, PoolStatus};
+use crate::state::store::Store;
+use crate::instructions::create_pool::{resolution_deadline, validate_pool_schedule};
+use crate::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdatePoolScheduleParams {
+    pub start_time: i64,
+    pub lock_time: i64,
+    pub end_time: i64,
+}
+
+#[derive(Accounts)]
+#[instruction(params: UpdatePoolScheduleParams)]
+pub struct UpdatePoolSchedule<'info> {
+    #[account(mut, has_one = creator)]
+    pub bet_pool: Account<'info, BetPool>,
+    pub creator: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+}
+
+#[error_code(offset = 7000)]
+pub enum UpdatePoolScheduleError {
+    #[msg("Pool is not open")] 
+    PoolNotOpen,
+    #[msg("Start time cannot change once bets exist")] 
+    StartTimeLocked,
+    #[msg("Lock and end time can only be extended once bets exist")] 
+    ScheduleShortened,
+}
+
+impl<'info> UpdatePoolSchedule<'info> {
+    pub fn apply(ctx: &mut Context<Self>, params: &UpdatePoolScheduleParams) -> Result<()> {
+        let pool = &mut ctx.accounts.bet_pool;
+        let clock = Clock::get()?;
+
+        pool.sync_status(clock.unix_timestamp);
+        require!(pool.status == PoolStatus::Open, UpdatePoolScheduleError::PoolNotOpen);
+        validate_pool_schedule(&ctx.accounts.store, params.start_time, params.lock_time, params.end_time, clock.unix_timestamp)?;
+
+        // Once someone has bet, the schedule they agreed to may only move later
+        if pool.unique_bettors > 0 {
+            require!(params.start_time == pool.start_time, UpdatePoolScheduleError::StartTimeLocked);
+            require!(params.lock_time >= pool.lock_time && params.end_time >= pool.end_time, UpdatePoolScheduleError::ScheduleShortened);
+        }
+
+        pool.start_time = params.start_time;
+        pool.lock_time = params.lock_time;
+        pool.end_time = params.end_time;
+        pool.resolution_deadline = resolution_deadline(params.end_time)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::create_pool::CreatePoolError;
+    use crate::test_utils::*;
+
+    // An open pool of `creator` running NOW..NOW + 2h, locking at NOW + 1h
+    fn open_pool(creator: Pubkey, unique_bettors: u64) -> BetPool {
+        BetPool {
+            creator,
+            start_time: NOW,
+            lock_time: NOW + HOUR,
+            end_time: NOW + 2 * HOUR,
+            resolution_deadline: NOW + 2 * HOUR + BetPool::RESOLUTION_WINDOW,
+            unique_bettors,
+            ..bet_pool(PoolStatus::Open)
+        }
+    }
+
+    // Moves the pool's schedule to (start, lock, end) at NOW, returning the updated pool
+    fn update(pool: BetPool, start_time: i64, lock_time: i64, end_time: i64) -> Result<BetPool> {
+        set_now(NOW);
+        let creator = pool.creator;
+        let mut accounts = UpdatePoolSchedule {
+            bet_pool: load(program_account(Pubkey::new_unique(), &pool, 8 + BetPool::INIT_SPACE)),
+            creator: signer(creator),
+            store: store_account(&store()),
+        };
+        let mut ctx = Context::new(&crate::ID, &mut accounts, &[], Default::default());
+        UpdatePoolSchedule::apply(&mut ctx, &UpdatePoolScheduleParams { start_time, lock_time, end_time })?;
+        Ok((*accounts.bet_pool).clone())
+    }
+
+    #[test]
+    fn schedules_move_freely_before_any_bet() {
+        let creator = Pubkey::new_unique();
+        // Later start, earlier lock and end
+        let pool = update(open_pool(creator, 0), NOW + HOUR, NOW + HOUR, NOW + 90 * 60).unwrap();
+        assert_eq!((pool.start_time, pool.lock_time, pool.end_time), (NOW + HOUR, NOW + HOUR, NOW + 90 * 60));
+        assert_eq!(pool.resolution_deadline, NOW + 90 * 60 + BetPool::RESOLUTION_WINDOW);
+
+        // Earlier start, later lock and end
+        let pool = update(pool, NOW - HOUR, NOW + 3 * HOUR, NOW + 4 * HOUR).unwrap();
+        assert_eq!((pool.start_time, pool.lock_time, pool.end_time), (NOW - HOUR, NOW + 3 * HOUR, NOW + 4 * HOUR));
+    }
+
+    #[test]
+    fn schedules_with_bets_can_only_be_extended() {
+        let pool = open_pool(Pubkey::new_unique(), 1);
+        let err = update(pool.clone(), NOW + 1, NOW + HOUR, NOW + 2 * HOUR).unwrap_err();
+        assert_eq!(err, UpdatePoolScheduleError::StartTimeLocked.into());
+        let err = update(pool.clone(), NOW, NOW + HOUR - 1, NOW + 2 * HOUR).unwrap_err();
+        assert_eq!(err, UpdatePoolScheduleError::ScheduleShortened.into());
+        let err = update(pool.clone(), NOW, NOW + HOUR, NOW + 2 * HOUR - 1).unwrap_err();
+        assert_eq!(err, UpdatePoolScheduleError::ScheduleShortened.into());
+
+        let pool = update(pool, NOW, NOW + 2 * HOUR, NOW + 3 * HOUR).unwrap();
+        assert_eq!((pool.start_time, pool.lock_time, pool.end_time), (NOW, NOW + 2 * HOUR, NOW + 3 * HOUR));
+    }
+
+    #[test]
+    fn schedules_are_validated_like_new_pools() {
+        let pool = open_pool(Pubkey::new_unique(), 0);
+        let err = update(pool.clone(), NOW + HOUR, NOW, NOW + 2 * HOUR).unwrap_err();
+        assert_eq!(err, CreatePoolError::InvalidTiming.into());
+        let err = update(pool.clone(), NOW, NOW + HOUR, NOW + HOUR / 2).unwrap_err();
+        assert_eq!(err, CreatePoolError::InvalidTiming.into());
+        let err = update(pool.clone(), NOW - 2 * HOUR, NOW - HOUR, NOW).unwrap_err();
+        assert_eq!(err, CreatePoolError::EndTimeInPast.into());
+        let err = update(pool, NOW, NOW + 60, NOW + 60).unwrap_err();
+        assert_eq!(err, CreatePoolError::DurationTooShort.into());
+    }
+
+    #[test]
+    fn closed_pools_keep_their_schedule() {
+        // Betting closed at the lock time, even though nobody cranked the pool
+        let pool = BetPool { lock_time: NOW, ..open_pool(Pubkey::new_unique(), 0) };
+        let err = update(pool, NOW, NOW + HOUR, NOW + 2 * HOUR).unwrap_err();
+        assert_eq!(err, UpdatePoolScheduleError::PoolNotOpen.into());
+    }
+}
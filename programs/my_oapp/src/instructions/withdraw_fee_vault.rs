@@ -0,0 +1,39 @@
+use crate::*;
+use anchor_lang::system_program;
+
+// The fee vault is prefunded with plain SOL transfers to its address and pays for acks to peers
+// using AckMode::FeeVault. The admin can take back what isn't needed.
+
+#[derive(Accounts)]
+pub struct WithdrawFeeVault<'info> {
+    #[account(mut, address = store.admin)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [STORE_SEED], bump = store.bump)]
+    pub store: Account<'info, Store>,
+    #[account(mut, seeds = [FEE_VAULT_SEED, &store.key().to_bytes()], bump)]
+    pub fee_vault: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl WithdrawFeeVault<'_> {
+    pub fn apply(ctx: &mut Context<WithdrawFeeVault>, params: &WithdrawFeeVaultParams) -> Result<()> {
+        let store_key = ctx.accounts.store.key();
+        let seeds: &[&[u8]] = &[FEE_VAULT_SEED, store_key.as_ref(), &[ctx.bumps.fee_vault]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    to: ctx.accounts.admin.to_account_info(),
+                },
+                &[seeds],
+            ),
+            params.amount,
+        )
+    }
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct WithdrawFeeVaultParams {
+    pub amount: u64,
+}
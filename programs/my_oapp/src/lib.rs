@@ -1,11 +1,15 @@
 mod errors;
 mod instructions;
 mod msg_codec;
+mod oft;
+mod pda;
 mod state;
+#[cfg(test)]
+mod test_utils;
 
 use anchor_lang::prelude::*;
 use instructions::*;
-use oapp::{endpoint::MessagingFee, endpoint_cpi::LzAccount, LzReceiveParams};
+use oapp::{endpoint::MessagingFee, endpoint_cpi::LzAccount, LzComposeParams, LzReceiveParams};
 use solana_helper::program_id_from_env;
 use state::*;
 
@@ -14,6 +18,11 @@ use crate::instructions::create_pool::{CreatePool, CreatePoolParams};
 use crate::instructions::place_bet::{PlaceBet, PlaceBetParams};
 use crate::instructions::resolve_market::{ResolveMarket, ResolveMarketParams};
 use crate::instructions::claim_winnings::{ClaimWinnings, ClaimWinningsParams};
+use crate::instructions::expire_unresolved::{ExpireUnresolved, ExpireUnresolvedParams};
+use crate::instructions::refund_bet::{RefundBet, RefundBetParams};
+use crate::instructions::cash_out::{CashOut, CashOutParams};
+use crate::instructions::close_betting::{CloseBetting, CloseBettingParams};
+use crate::instructions::update_pool_schedule::{UpdatePoolSchedule, UpdatePoolScheduleParams};
 
 // to build in verifiable mode and using environment variable (what the README instructs), run:
 // anchor build -v -e MYOAPP_ID=<OAPP_PROGRAM_ID>
@@ -25,8 +34,18 @@ declare_id!(anchor_lang::solana_program::pubkey::Pubkey::new_from_array(program_
 )));
 
 const LZ_RECEIVE_TYPES_SEED: &[u8] = b"LzReceiveTypes"; // The Executor relies on this exact seed to derive the LzReceiveTypes PDA. Keep it the same.
+const LZ_COMPOSE_TYPES_SEED: &[u8] = b"LzComposeTypes"; // The Executor relies on this exact seed to derive the LzComposeTypes PDA. Keep it the same.
 const STORE_SEED: &[u8] = b"Store"; // You are free to edit this seed.
 const PEER_SEED: &[u8] = b"Peer"; // The Executor relies on this exact seed to derive the LzReceiveTypes PDA. Keep it the same.
+const POOL_VAULT_SEED: &[u8] = b"PoolVault";
+const NONCE_SEED: &[u8] = b"Nonce";
+const FAILED_MESSAGE_SEED: &[u8] = b"FailedMessage";
+const FEE_VAULT_SEED: &[u8] = b"FeeVault";
+const IDENTITY_LINK_SEED: &[u8] = b"IdentityLink";
+const BET_POOL_SEED: &[u8] = b"betpool";
+const BET_SEED: &[u8] = b"bet";
+const CREATOR_POOL_INDEX_SEED: &[u8] = b"CreatorPoolIndex";
+const RESPONSE_ACCOUNTS_SEED: &[u8] = b"ResponseAccounts";
 
 #[program]
 pub mod my_oapp {
@@ -47,22 +66,70 @@ pub mod my_oapp {
         SetPeerConfig::apply(&mut ctx, &params)
     }
 
+    // admin instruction to update Store-wide settings.
+    pub fn set_store_config(
+        mut ctx: Context<SetStoreConfig>,
+        params: SetStoreConfigParams,
+    ) -> Result<()> {
+        SetStoreConfig::apply(&mut ctx, &params)
+    }
+
+    // admin instruction to skip an inbound nonce that can never be executed, unblocking ordered peers.
+    pub fn skip_inbound_nonce(
+        mut ctx: Context<SkipInboundNonce>,
+        params: SkipInboundNonceParams,
+    ) -> Result<()> {
+        SkipInboundNonce::apply(&mut ctx, &params)
+    }
+
+    // admin or pauser instruction to freeze the program or a single peer during an incident.
+    pub fn set_pause(mut ctx: Context<SetPause>, params: SetPauseParams) -> Result<()> {
+        SetPause::apply(&mut ctx, &params)
+    }
+
+    // admin instruction to register the accounts lz_receive responds to a remote chain with.
+    pub fn set_response_accounts(
+        mut ctx: Context<SetResponseAccounts>,
+        params: SetResponseAccountsParams,
+    ) -> Result<()> {
+        SetResponseAccounts::apply(&mut ctx, &params)
+    }
+
+    // admin instruction to withdraw SOL from the fee vault that pays for acks.
+    pub fn withdraw_fee_vault(
+        mut ctx: Context<WithdrawFeeVault>,
+        params: WithdrawFeeVaultParams,
+    ) -> Result<()> {
+        WithdrawFeeVault::apply(&mut ctx, &params)
+    }
+
     // ============================== Public ==============================
     // public instruction returning the estimated MessagingFee for sending a message.
     pub fn quote_send(ctx: Context<QuoteSend>, params: QuoteSendParams) -> Result<MessagingFee> {
         QuoteSend::apply(&ctx, &params)
     }
 
-    // public instruction to send a message to a cross-chain peer.
+    // admin instruction to send a message to a cross-chain peer.
     pub fn send(mut ctx: Context<Send>, params: SendMessageParams) -> Result<()> {
         Send::apply(&mut ctx, &params)
     }
 
     // handler for processing incoming cross-chain messages and executing the LzReceive logic
-    pub fn lz_receive(mut ctx: Context<LzReceive>, params: LzReceiveParams) -> Result<()> {
+    pub fn lz_receive<'info>(
+        mut ctx: Context<'_, '_, 'info, 'info, LzReceive<'info>>,
+        params: LzReceiveParams,
+    ) -> Result<()> {
         LzReceive::apply(&mut ctx, &params)
     }
 
+    // public instruction to execute a message that failed in a non-blocking lz_receive.
+    pub fn retry_failed_message<'info>(
+        mut ctx: Context<'_, '_, 'info, 'info, RetryFailedMessage<'info>>,
+        params: RetryFailedMessageParams,
+    ) -> Result<()> {
+        RetryFailedMessage::apply(&mut ctx, &params)
+    }
+
     // handler that returns the list of accounts required to execute lz_receive
     pub fn lz_receive_types(
         ctx: Context<LzReceiveTypes>,
@@ -71,13 +138,47 @@ pub mod my_oapp {
         LzReceiveTypes::apply(&ctx, &params)
     }
 
+    // view returning the nonce the Executor must deliver next from a peer, or 0 if the peer is unordered
+    pub fn next_nonce(ctx: Context<NextNonce>, params: NextNonceParams) -> Result<u64> {
+        NextNonce::apply(&ctx, &params)
+    }
+
+    // handler for OFT compose messages that deliver cross-chain stakes
+    pub fn lz_compose<'info>(
+        mut ctx: Context<'_, '_, 'info, 'info, LzCompose<'info>>,
+        params: LzComposeParams,
+    ) -> Result<()> {
+        LzCompose::apply(&mut ctx, &params)
+    }
+
+    // returns the accounts the Executor must pass to lz_compose for a given compose message
+    pub fn lz_compose_types(
+        ctx: Context<LzComposeTypes>,
+        params: LzComposeParams,
+    ) -> Result<Vec<LzAccount>> {
+        LzComposeTypes::apply(&ctx, &params)
+    }
+
     // ============================== Betlify ==============================
+    // creates a pool under the next global pool id, indexed by the creator's own params.creator_pool_id
     pub fn create_pool(
         mut ctx: Context<CreatePool>,
         params: CreatePoolParams,
-        pool_id: u64,
     ) -> Result<()> {
-        CreatePool::apply(&mut ctx, &params, pool_id)
+        CreatePool::apply(&mut ctx, &params)
+    }
+
+    // creator instruction to move a pool's schedule; only extensions are allowed once bets exist
+    pub fn update_pool_schedule(
+        mut ctx: Context<UpdatePoolSchedule>,
+        params: UpdatePoolScheduleParams,
+    ) -> Result<()> {
+        UpdatePoolSchedule::apply(&mut ctx, &params)
+    }
+
+    // creates the token vault that holds a pool's cross-chain stakes
+    pub fn init_pool_vault(mut ctx: Context<InitPoolVault>) -> Result<()> {
+        InitPoolVault::apply(&mut ctx)
     }
 
     pub fn place_bet(
@@ -100,4 +201,35 @@ pub mod my_oapp {
     ) -> Result<()> {
         ClaimWinnings::apply(&mut ctx, &params)
     }
+
+    // permissionless instruction to void a pool left unresolved past its resolution deadline
+    pub fn expire_unresolved(
+        mut ctx: Context<ExpireUnresolved>,
+        params: ExpireUnresolvedParams,
+    ) -> Result<()> {
+        ExpireUnresolved::apply(&mut ctx, &params)
+    }
+
+    pub fn refund_bet(
+        mut ctx: Context<RefundBet>,
+        params: RefundBetParams,
+    ) -> Result<()> {
+        RefundBet::apply(&mut ctx, &params)
+    }
+
+    // withdraws a bet, and its stake, from a pool that is still taking bets
+    pub fn cash_out(
+        mut ctx: Context<CashOut>,
+        params: CashOutParams,
+    ) -> Result<()> {
+        CashOut::apply(&mut ctx, &params)
+    }
+
+    // permissionless crank moving a pool from Open to Closed once its lock_time has passed
+    pub fn close_betting(
+        mut ctx: Context<CloseBetting>,
+        params: CloseBettingParams,
+    ) -> Result<()> {
+        CloseBetting::apply(&mut ctx, &params)
+    }
 }
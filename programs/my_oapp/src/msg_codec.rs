@@ -1,88 +1,690 @@
-use anchor_lang::prelude::error_code;
-use std::str;
 use anchor_lang::prelude::*;
+use std::str;
+
+use crate::state::{MAX_EVIDENCE_URI_LEN, MAX_OPTIONS, MAX_QUESTION_LEN, MAX_RESOLUTION_SOURCE_LEN};
 
 // -----------------------------------------------------------------------------
-// This file defines how the example program encodes and decodes its messages.
-// Each OApp can implement its own layout as long as the sending and receiving
-// chains agree.  Here we simply prefix a UTF-8 string with a 32 byte length
-// header. In this example, the EVM-side equivalant is in `contracts/libs/StringMsgCodec.sol`
+// This file defines how the program encodes and decodes its messages. Each OApp can implement
+// its own layout as long as the sending and receiving chains agree. The EVM-side equivalent is
+// in `contracts/libs/BetlifyMsgCodec.sol`.
 // -----------------------------------------------------------------------------
 
-
-// The message is a UTF-8 encoded string prefixed with a 32 byte header.
-// The following is the layout of the message:
-// Offset →
-// 0                     28     32                     32+N
-// |---------------------|------|---------------------------->
-// |     28 bytes        | 4B   |     N bytes                |
-// |    zero padding     | len  | UTF-8 encoded string       |
-// |---------------------|------|----------------------------|
-
-
-// We prefix the encoded string with a 32 byte length header.
-pub const LENGTH_OFFSET: usize = 0;
-pub const STRING_OFFSET: usize = 32;
-
-#[error_code]
+#[error_code(offset = 6100)]
 pub enum MsgCodecError {
-    /// Buffer too short to even contain the 32‐byte length header
-    InvalidLength,
-    /// Header says "string is N bytes" but buffer < 32+N
+    /// Message ends before a field it declares
+    #[msg("Message truncated")]
     BodyTooShort,
     /// Payload bytes aren't valid UTF-8
+    #[msg("String is not valid UTF-8")]
     InvalidUtf8,
+    /// Header carries a version this program can't decode
+    #[msg("Unsupported message version")]
+    UnsupportedVersion,
+    /// Header carries a message type this version doesn't define
+    #[msg("Unknown message type")]
+    UnknownMessageType,
+    /// Bytes left over after the body was fully decoded
+    #[msg("Trailing bytes after message body")]
+    TrailingBytes,
+    /// Declared string length is over the limit for that field
+    #[msg("String too long")]
+    StringTooLong,
+    /// Declared option count is over MAX_OPTIONS
+    #[msg("Too many options")]
+    TooManyOptions,
+    /// Batch is empty or holds more than MAX_BATCH_LEN items
+    #[msg("Invalid batch length")]
+    InvalidBatchLength,
+    /// Batch item is itself a batch
+    #[msg("Batches can't be nested")]
+    NestedBatch,
 }
 
-// Encode a UTF-8 string into a message format with a 32 byte header
-pub fn encode(string: &str) -> Vec<u8> {
-    let string_bytes = string.as_bytes();
-    let mut msg = Vec::with_capacity(
-        STRING_OFFSET +               // header length
-        string_bytes.len()            // string bytes
-    );
+// -----------------------------------------------------------------------------
+// Betlify messages use the packed layout produced by `contracts/libs/BetlifyMsgCodec.sol`
+// (`abi.encodePacked`). Integers are big-endian, addresses are 20 bytes, strings are a 4 byte
+// length followed by UTF-8 bytes and string arrays are a 4 byte count followed by that many strings.
+//
+// Every message starts with a fixed header so the layout can evolve without breaking messages
+// already in flight. Decoding dispatches on the version byte and rejects versions it doesn't know.
+// Offset →
+// 0         1          2              10             18
+// |---------|----------|--------------|--------------|------------->
+// | version | msg_type | request_id   | timestamp    | body         |
+// | 1B      | 1B       | u64          | u64          | N bytes      |
+//
+// Version 1 bodies, by msg_type:
+// CreatePool:    creator 20B | question | options | start_time i64 | lock_time i64 | end_time i64 |
+//                creator_pool_id u64 | resolution_source
+// PlaceBet:      authority 20B | pool_id u64 | option u8 | amount u64
+// ResolveMarket: resolver 20B | pool_id u64 | winning_option u8 | evidence_uri | evidence_hash 32B
+// ClaimWinnings: authority 20B | pool_id u64
+// PayoutIssued:  pool_id u64 | recipient 32B | amount u64
+// Batch:         count u8 | count × (msg_type u8 | body)
+// MessageFailed: guid 32B | error_code u32
+// ActionAck:     guid 32B | status u8 | pool_id u64 | bet 32B
+// LinkWallet:    authority 20B | solana_pubkey 32B
+//
+// Pool ids other than CreatePool's are global ids allocated by Solana from Store.pools_count. The
+// ActionAck for a CreatePool reports the global id of the pool it created.
+//
+// Batch items share the batch's header and can't be batches themselves. Items may be for different
+// bettors and pools; lz_receive takes the accounts of each.
+//
+// Length prefixes are checked against the field's limit before anything is allocated, so a
+// hostile length can't make the program allocate more than the largest valid message needs.
+//
+// Golden vectors shared with the Foundry tests live in `test/vectors/betlify-msg-codec.json`.
+// -----------------------------------------------------------------------------
 
-    // 4 byte length stored at the end of the 32 byte header
-    msg.extend(std::iter::repeat(0).take(28)); // padding
-    msg.extend_from_slice(&(string_bytes.len() as u32).to_be_bytes());
+pub const VERSION_1: u8 = 1;
+pub const CURRENT_VERSION: u8 = VERSION_1;
 
-    // string
-    msg.extend_from_slice(string_bytes);
+pub const CREATE_POOL_TYPE: u8 = 0;
+pub const PLACE_BET_TYPE: u8 = 1;
+pub const RESOLVE_MARKET_TYPE: u8 = 2;
+pub const CLAIM_WINNINGS_TYPE: u8 = 3;
+pub const PAYOUT_ISSUED_TYPE: u8 = 4;
+pub const BATCH_TYPE: u8 = 5;
+pub const MESSAGE_FAILED_TYPE: u8 = 6;
+pub const ACTION_ACK_TYPE: u8 = 7;
+pub const LINK_WALLET_TYPE: u8 = 8;
 
-    msg
+// ActionAck statuses
+pub const ACK_EXECUTED: u8 = 0;
+pub const ACK_FAILED: u8 = 1; // Stored for retry by a non-blocking peer
+
+pub const MAX_BATCH_LEN: usize = 4;
+
+// Option labels aren't stored on-chain, this only bounds what a message can make us allocate
+pub const MAX_OPTION_LABEL_LEN: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageHeader {
+    pub version: u8,
+    pub msg_type: u8,
+    pub request_id: u64, // Chosen by the client that sent the action, echoed back in responses
+    pub timestamp: u64, // Source chain time the message was built at
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct BetlifyEnvelope {
+    pub header: MessageHeader,
+    pub message: BetlifyMessage,
+}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub enum BetlifyMessage {
     CreatePool {
+        creator: [u8; 20], // EVM address of the pool creator, the only one who may resolve it
         question: String,
         options: Vec<String>,
-        pool_id: u64,
         start_time: i64,
         lock_time: i64,
         end_time: i64,
+        creator_pool_id: u64, // The sender's own id for the pool; Solana allocates the global pool id
+        resolution_source: String, // Where the outcome will be taken from, like create_pool's
     },
     PlaceBet {
+        authority: [u8; 20], // EVM address of the bettor
         pool_id: u64,
         option: u8,
         amount: u64,
     },
     ResolveMarket {
+        resolver: [u8; 20], // EVM address resolving the pool, checked against its creator
         pool_id: u64,
         winning_option: u8,
+        evidence_uri: String, // Where the outcome can be checked, like resolve_market's
+        evidence_hash: [u8; 32],
     },
     ClaimWinnings {
+        authority: [u8; 20], // EVM address of the bettor
+        pool_id: u64,
+    },
+    // Outbound: tells the source chain adapter to release `amount` to `recipient`
+    PayoutIssued {
+        pool_id: u64,
+        recipient: [u8; 32],
+        amount: u64,
+    },
+    // Actions executed in order, all or nothing
+    Batch(BatchItems),
+    // Outbound: the message `guid` failed with `error_code` and was stored for retry.
+    // The header echoes the failed message's request id.
+    MessageFailed {
+        guid: [u8; 32],
+        error_code: u32,
+    },
+    // Outbound: the outcome of message `guid`, with the pool and bet PDA it left its actions on.
+    // The header echoes the message's request id.
+    ActionAck {
+        guid: [u8; 32],
+        status: u8,
         pool_id: u64,
+        bet: [u8; 32],
+    },
+    // Lets `solana_pubkey` manage the authority's cross-chain bets from Solana; all zeroes unlinks
+    LinkWallet {
+        authority: [u8; 20], // EVM address of the bettor
+        solana_pubkey: [u8; 32],
     },
 }
 
-pub fn encode_betlify_message(msg: &BetlifyMessage) -> Vec<u8> {
-    let mut data = vec![];
-    msg.serialize(&mut data).unwrap();
+// Items of a Batch. The newtype breaks the recursion for Anchor 0.29's IDL builder, which
+// otherwise recurses forever on a type that contains itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchItems(pub Vec<BetlifyMessage>);
+
+impl AnchorSerialize for BatchItems {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for BatchItems {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Vec::<BetlifyMessage>::deserialize_reader(reader).map(BatchItems)
+    }
+}
+
+#[cfg(feature = "idl-build")]
+impl anchor_lang::IdlBuild for BatchItems {}
+
+impl BetlifyMessage {
+    pub fn msg_type(&self) -> u8 {
+        match self {
+            BetlifyMessage::CreatePool { .. } => CREATE_POOL_TYPE,
+            BetlifyMessage::PlaceBet { .. } => PLACE_BET_TYPE,
+            BetlifyMessage::ResolveMarket { .. } => RESOLVE_MARKET_TYPE,
+            BetlifyMessage::ClaimWinnings { .. } => CLAIM_WINNINGS_TYPE,
+            BetlifyMessage::PayoutIssued { .. } => PAYOUT_ISSUED_TYPE,
+            BetlifyMessage::Batch(_) => BATCH_TYPE,
+            BetlifyMessage::MessageFailed { .. } => MESSAGE_FAILED_TYPE,
+            BetlifyMessage::ActionAck { .. } => ACTION_ACK_TYPE,
+            BetlifyMessage::LinkWallet { .. } => LINK_WALLET_TYPE,
+        }
+    }
+
+    // The actions to execute, in order: a batch's items, or the message itself
+    pub fn actions(&self) -> &[BetlifyMessage] {
+        match self {
+            BetlifyMessage::Batch(items) => &items.0,
+            msg => std::slice::from_ref(msg),
+        }
+    }
+
+    // The EVM bettor an action is taken for, if it acts on a bettor's accounts
+    pub fn authority(&self) -> Option<[u8; 20]> {
+        match self {
+            BetlifyMessage::PlaceBet { authority, .. }
+            | BetlifyMessage::ClaimWinnings { authority, .. }
+            | BetlifyMessage::LinkWallet { authority, .. } => Some(*authority),
+            _ => None,
+        }
+    }
+}
+
+// A remote user as stored on Solana: the EVM address left-padded to 32 bytes, like bytes32(uint256(uint160(a)))
+pub fn remote_user(authority: [u8; 20]) -> [u8; 32] {
+    let mut user = [0u8; 32];
+    user[12..].copy_from_slice(&authority);
+    user
+}
+
+// The remote user the first bettor action of an encoded message is for, or all zeroes if it has none
+// or doesn't decode. Used in lz_compose PDA seeds, where the message hasn't been decoded yet.
+pub fn message_user(data: &[u8]) -> [u8; 32] {
+    decode_betlify_message(data)
+        .ok()
+        .and_then(|message| message.actions().iter().find_map(BetlifyMessage::authority))
+        .map(remote_user)
+        .unwrap_or_default()
+}
+
+// Encodes `msg` with a current-version header
+pub fn encode_betlify_message(request_id: u64, timestamp: u64, msg: &BetlifyMessage) -> Vec<u8> {
+    let mut data = vec![CURRENT_VERSION, msg.msg_type()];
+    data.extend_from_slice(&request_id.to_be_bytes());
+    data.extend_from_slice(&timestamp.to_be_bytes());
+    write_body(&mut data, msg);
     data
 }
 
+fn write_body(data: &mut Vec<u8>, msg: &BetlifyMessage) {
+    match msg {
+        BetlifyMessage::CreatePool {
+            creator,
+            question,
+            options,
+            start_time,
+            lock_time,
+            end_time,
+            creator_pool_id,
+            resolution_source,
+        } => {
+            data.extend_from_slice(creator);
+            write_string(data, question);
+            data.extend_from_slice(&(options.len() as u32).to_be_bytes());
+            for option in options {
+                write_string(data, option);
+            }
+            data.extend_from_slice(&start_time.to_be_bytes());
+            data.extend_from_slice(&lock_time.to_be_bytes());
+            data.extend_from_slice(&end_time.to_be_bytes());
+            data.extend_from_slice(&creator_pool_id.to_be_bytes());
+            write_string(data, resolution_source);
+        },
+        BetlifyMessage::PlaceBet { authority, pool_id, option, amount } => {
+            data.extend_from_slice(authority);
+            data.extend_from_slice(&pool_id.to_be_bytes());
+            data.push(*option);
+            data.extend_from_slice(&amount.to_be_bytes());
+        },
+        BetlifyMessage::ResolveMarket { resolver, pool_id, winning_option, evidence_uri, evidence_hash } => {
+            data.extend_from_slice(resolver);
+            data.extend_from_slice(&pool_id.to_be_bytes());
+            data.push(*winning_option);
+            write_string(data, evidence_uri);
+            data.extend_from_slice(evidence_hash);
+        },
+        BetlifyMessage::ClaimWinnings { authority, pool_id } => {
+            data.extend_from_slice(authority);
+            data.extend_from_slice(&pool_id.to_be_bytes());
+        },
+        BetlifyMessage::PayoutIssued { pool_id, recipient, amount } => {
+            data.extend_from_slice(&pool_id.to_be_bytes());
+            data.extend_from_slice(recipient);
+            data.extend_from_slice(&amount.to_be_bytes());
+        },
+        BetlifyMessage::Batch(items) => {
+            data.push(items.0.len() as u8);
+            for item in &items.0 {
+                data.push(item.msg_type());
+                write_body(data, item);
+            }
+        },
+        BetlifyMessage::MessageFailed { guid, error_code } => {
+            data.extend_from_slice(guid);
+            data.extend_from_slice(&error_code.to_be_bytes());
+        },
+        BetlifyMessage::ActionAck { guid, status, pool_id, bet } => {
+            data.extend_from_slice(guid);
+            data.push(*status);
+            data.extend_from_slice(&pool_id.to_be_bytes());
+            data.extend_from_slice(bet);
+        },
+        BetlifyMessage::LinkWallet { authority, solana_pubkey } => {
+            data.extend_from_slice(authority);
+            data.extend_from_slice(solana_pubkey);
+        },
+    }
+}
+
+pub fn decode_betlify_envelope(data: &[u8]) -> std::result::Result<BetlifyEnvelope, MsgCodecError> {
+    let mut reader = Reader { data, offset: 0 };
+    let envelope = match reader.read_u8()? {
+        VERSION_1 => decode_v1(&mut reader)?,
+        _ => return Err(MsgCodecError::UnsupportedVersion),
+    };
+    // Packed messages have no padding, so anything left over means the layouts disagree
+    if reader.offset != data.len() {
+        return Err(MsgCodecError::TrailingBytes);
+    }
+    Ok(envelope)
+}
+
 pub fn decode_betlify_message(data: &[u8]) -> std::result::Result<BetlifyMessage, MsgCodecError> {
-    BetlifyMessage::try_from_slice(data).map_err(|_| MsgCodecError::InvalidUtf8)
+    decode_betlify_envelope(data).map(|envelope| envelope.message)
+}
+
+fn decode_v1(reader: &mut Reader) -> std::result::Result<BetlifyEnvelope, MsgCodecError> {
+    let header = MessageHeader {
+        version: VERSION_1,
+        msg_type: reader.read_u8()?,
+        request_id: reader.read_u64()?,
+        timestamp: reader.read_u64()?,
+    };
+    let message = match header.msg_type {
+        BATCH_TYPE => {
+            let count = reader.read_u8()? as usize;
+            if count == 0 || count > MAX_BATCH_LEN {
+                return Err(MsgCodecError::InvalidBatchLength);
+            }
+            let mut items = Vec::with_capacity(count);
+            for _ in 0..count {
+                match reader.read_u8()? {
+                    BATCH_TYPE => return Err(MsgCodecError::NestedBatch),
+                    msg_type => items.push(decode_v1_body(reader, msg_type)?),
+                }
+            }
+            BetlifyMessage::Batch(BatchItems(items))
+        },
+        msg_type => decode_v1_body(reader, msg_type)?,
+    };
+    Ok(BetlifyEnvelope { header, message })
+}
+
+fn decode_v1_body(reader: &mut Reader, msg_type: u8) -> std::result::Result<BetlifyMessage, MsgCodecError> {
+    let message = match msg_type {
+        CREATE_POOL_TYPE => {
+            let creator = reader.read_array()?;
+            let question = reader.read_string(MAX_QUESTION_LEN)?;
+            let options_len = reader.read_u32()? as usize;
+            if options_len > MAX_OPTIONS {
+                return Err(MsgCodecError::TooManyOptions);
+            }
+            let mut options = Vec::with_capacity(options_len);
+            for _ in 0..options_len {
+                options.push(reader.read_string(MAX_OPTION_LABEL_LEN)?);
+            }
+            BetlifyMessage::CreatePool {
+                creator,
+                question,
+                options,
+                start_time: reader.read_i64()?,
+                lock_time: reader.read_i64()?,
+                end_time: reader.read_i64()?,
+                creator_pool_id: reader.read_u64()?,
+                resolution_source: reader.read_string(MAX_RESOLUTION_SOURCE_LEN)?,
+            }
+        },
+        PLACE_BET_TYPE => BetlifyMessage::PlaceBet {
+            authority: reader.read_array()?,
+            pool_id: reader.read_u64()?,
+            option: reader.read_u8()?,
+            amount: reader.read_u64()?,
+        },
+        RESOLVE_MARKET_TYPE => BetlifyMessage::ResolveMarket {
+            resolver: reader.read_array()?,
+            pool_id: reader.read_u64()?,
+            winning_option: reader.read_u8()?,
+            evidence_uri: reader.read_string(MAX_EVIDENCE_URI_LEN)?,
+            evidence_hash: reader.read_array()?,
+        },
+        CLAIM_WINNINGS_TYPE => BetlifyMessage::ClaimWinnings {
+            authority: reader.read_array()?,
+            pool_id: reader.read_u64()?,
+        },
+        PAYOUT_ISSUED_TYPE => BetlifyMessage::PayoutIssued {
+            pool_id: reader.read_u64()?,
+            recipient: reader.read_array()?,
+            amount: reader.read_u64()?,
+        },
+        MESSAGE_FAILED_TYPE => BetlifyMessage::MessageFailed {
+            guid: reader.read_array()?,
+            error_code: reader.read_u32()?,
+        },
+        ACTION_ACK_TYPE => BetlifyMessage::ActionAck {
+            guid: reader.read_array()?,
+            status: reader.read_u8()?,
+            pool_id: reader.read_u64()?,
+            bet: reader.read_array()?,
+        },
+        LINK_WALLET_TYPE => BetlifyMessage::LinkWallet {
+            authority: reader.read_array()?,
+            solana_pubkey: reader.read_array()?,
+        },
+        _ => return Err(MsgCodecError::UnknownMessageType),
+    };
+    Ok(message)
+}
+
+fn write_string(data: &mut Vec<u8>, string: &str) {
+    data.extend_from_slice(&(string.len() as u32).to_be_bytes());
+    data.extend_from_slice(string.as_bytes());
+}
+
+// Cursor over a packed message
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> std::result::Result<&'a [u8], MsgCodecError> {
+        let end = self.offset.checked_add(len).ok_or(MsgCodecError::BodyTooShort)?;
+        let bytes = self.data.get(self.offset..end).ok_or(MsgCodecError::BodyTooShort)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> std::result::Result<[u8; N], MsgCodecError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self) -> std::result::Result<u8, MsgCodecError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> std::result::Result<u32, MsgCodecError> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    fn read_u64(&mut self) -> std::result::Result<u64, MsgCodecError> {
+        Ok(u64::from_be_bytes(self.read_array()?))
+    }
+
+    fn read_i64(&mut self) -> std::result::Result<i64, MsgCodecError> {
+        Ok(i64::from_be_bytes(self.read_array()?))
+    }
+
+    fn read_string(&mut self, max_len: usize) -> std::result::Result<String, MsgCodecError> {
+        let len = self.read_u32()? as usize;
+        if len > max_len {
+            return Err(MsgCodecError::StringTooLong);
+        }
+        let bytes = self.read_bytes(len)?;
+        str::from_utf8(bytes).map(str::to_string).map_err(|_| MsgCodecError::InvalidUtf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Same vectors the Foundry tests check BetlifyMsgCodec.sol against
+    const VECTORS: &str = include_str!("../../../test/vectors/betlify-msg-codec.json");
+
+    fn vector(name: &str) -> Vec<u8> {
+        let vectors: serde_json::Value = serde_json::from_str(VECTORS).unwrap();
+        hex::decode(vectors[name].as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    fn authority() -> [u8; 20] {
+        let mut authority = [0u8; 20];
+        authority[18..].copy_from_slice(&[0x12, 0x34]);
+        authority
+    }
+
+    const REQUEST_ID: u64 = 7;
+    const TIMESTAMP: u64 = 1700000000;
+
+    fn assert_round_trip(name: &str, msg: BetlifyMessage) {
+        let bytes = vector(name);
+        let envelope = decode_betlify_envelope(&bytes).unwrap();
+        assert_eq!(
+            envelope.header,
+            MessageHeader {
+                version: CURRENT_VERSION,
+                msg_type: msg.msg_type(),
+                request_id: REQUEST_ID,
+                timestamp: TIMESTAMP,
+            }
+        );
+        assert_eq!(envelope.message, msg);
+        assert_eq!(encode_betlify_message(REQUEST_ID, TIMESTAMP, &msg), bytes);
+    }
+
+    #[test]
+    fn create_pool_matches_solidity() {
+        assert_round_trip(
+            "createPool",
+            BetlifyMessage::CreatePool {
+                creator: authority(),
+                question: "Who will win?".to_string(),
+                options: vec!["A".to_string(), "B".to_string()],
+                start_time: 1700000000,
+                lock_time: 1700003600,
+                end_time: 1700007200,
+                creator_pool_id: 42,
+                resolution_source: "espn.com".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn place_bet_matches_solidity() {
+        assert_round_trip(
+            "placeBet",
+            BetlifyMessage::PlaceBet { authority: authority(), pool_id: 99, option: 1, amount: 1000 },
+        );
+    }
+
+    #[test]
+    fn resolve_market_matches_solidity() {
+        let mut evidence_hash = [0u8; 32];
+        evidence_hash[30..].copy_from_slice(&[0x12, 0x34]);
+        assert_round_trip(
+            "resolveMarket",
+            BetlifyMessage::ResolveMarket {
+                resolver: authority(),
+                pool_id: 99,
+                winning_option: 1,
+                evidence_uri: "ipfs://evidence".to_string(),
+                evidence_hash,
+            },
+        );
+    }
+
+    #[test]
+    fn claim_winnings_matches_solidity() {
+        assert_round_trip("claimWinnings", BetlifyMessage::ClaimWinnings { authority: authority(), pool_id: 99 });
+    }
+
+    #[test]
+    fn payout_issued_matches_solidity() {
+        let mut recipient = [0u8; 32];
+        recipient[30..].copy_from_slice(&[0x12, 0x34]);
+        assert_round_trip("payoutIssued", BetlifyMessage::PayoutIssued { pool_id: 99, recipient, amount: 1000 });
+    }
+
+    #[test]
+    fn rejects_truncated_and_padded_messages() {
+        let bytes = vector("placeBet");
+        assert!(matches!(decode_betlify_message(&bytes[..bytes.len() - 1]), Err(MsgCodecError::BodyTooShort)));
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(matches!(decode_betlify_message(&padded), Err(MsgCodecError::TrailingBytes)));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut bytes = vector("placeBet");
+        bytes[0] = CURRENT_VERSION + 1;
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::UnsupportedVersion)));
+    }
+
+    #[test]
+    fn rejects_unknown_message_types() {
+        let mut bytes = vector("claimWinnings");
+        bytes[1] = LINK_WALLET_TYPE + 1;
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::UnknownMessageType)));
+    }
+
+    #[test]
+    fn message_failed_matches_solidity() {
+        let mut guid = [0u8; 32];
+        guid[30..].copy_from_slice(&[0xab, 0xcd]);
+        assert_round_trip("messageFailed", BetlifyMessage::MessageFailed { guid, error_code: 6002 });
+    }
+
+    #[test]
+    fn action_ack_matches_solidity() {
+        let mut guid = [0u8; 32];
+        guid[30..].copy_from_slice(&[0xab, 0xcd]);
+        let mut bet = [0u8; 32];
+        bet[30..].copy_from_slice(&[0x12, 0x34]);
+        assert_round_trip(
+            "actionAck",
+            BetlifyMessage::ActionAck { guid, status: ACK_EXECUTED, pool_id: 99, bet },
+        );
+    }
+
+    #[test]
+    fn link_wallet_matches_solidity() {
+        let mut solana_pubkey = [0u8; 32];
+        solana_pubkey[30..].copy_from_slice(&[0x56, 0x78]);
+        assert_round_trip("linkWallet", BetlifyMessage::LinkWallet { authority: authority(), solana_pubkey });
+    }
+
+    #[test]
+    fn batch_matches_solidity() {
+        assert_round_trip(
+            "batch",
+            BetlifyMessage::Batch(BatchItems(vec![
+                BetlifyMessage::CreatePool {
+                    creator: authority(),
+                    question: "Who will win?".to_string(),
+                    options: vec!["A".to_string(), "B".to_string()],
+                    start_time: 1700000000,
+                    lock_time: 1700003600,
+                    end_time: 1700007200,
+                    creator_pool_id: 42,
+                    resolution_source: "espn.com".to_string(),
+                },
+                BetlifyMessage::PlaceBet { authority: authority(), pool_id: 42, option: 1, amount: 1000 },
+            ])),
+        );
+    }
+
+    #[test]
+    fn rejects_bad_batches() {
+        let claim = BetlifyMessage::ClaimWinnings { authority: authority(), pool_id: 99 };
+        let too_long = BetlifyMessage::Batch(BatchItems(vec![claim.clone(); MAX_BATCH_LEN + 1]));
+        let bytes = encode_betlify_message(REQUEST_ID, TIMESTAMP, &too_long);
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::InvalidBatchLength)));
+
+        let empty = BetlifyMessage::Batch(BatchItems(vec![]));
+        let bytes = encode_betlify_message(REQUEST_ID, TIMESTAMP, &empty);
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::InvalidBatchLength)));
+
+        let nested = BetlifyMessage::Batch(BatchItems(vec![BetlifyMessage::Batch(BatchItems(vec![claim]))]));
+        let bytes = encode_betlify_message(REQUEST_ID, TIMESTAMP, &nested);
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::NestedBatch)));
+    }
+
+    #[test]
+    fn message_user_is_the_padded_authority() {
+        let mut user = [0u8; 32];
+        user[30..].copy_from_slice(&[0x12, 0x34]);
+        assert_eq!(message_user(&vector("placeBet")), user);
+        assert_eq!(message_user(&vector("batch")), user);
+        assert_eq!(message_user(&vector("resolveMarket")), [0u8; 32]);
+    }
+
+    // CreatePool body starts right after the 18 byte header with the creator, then the question's length prefix
+    const QUESTION_OFFSET: usize = 18 + 20;
+
+    #[test]
+    fn rejects_oversized_strings_before_reading_them() {
+        let mut bytes = vector("createPool");
+        bytes[QUESTION_OFFSET..QUESTION_OFFSET + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::StringTooLong)));
+    }
+
+    #[test]
+    fn rejects_too_many_options() {
+        let mut bytes = vector("createPool");
+        let options_offset = QUESTION_OFFSET + 4 + "Who will win?".len();
+        bytes[options_offset..options_offset + 4].copy_from_slice(&(MAX_OPTIONS as u32 + 1).to_be_bytes());
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::TooManyOptions)));
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let mut bytes = vector("createPool");
+        bytes[QUESTION_OFFSET + 4] = 0xff;
+        assert!(matches!(decode_betlify_message(&bytes), Err(MsgCodecError::InvalidUtf8)));
+    }
 }
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+
+// -----------------------------------------------------------------------------
+// Minimal client for the LayerZero OFT program that bridges Betlify stakes.
+// We only need two things from it: reading the compose message it forwards when
+// tokens arrive for us, and calling its `send` instruction to pay winners out.
+// -----------------------------------------------------------------------------
+
+// The OFT compose message is laid out as follows (big-endian integers):
+// Offset →
+// 0        8         12          20                52
+// |--------|---------|-----------|-----------------|-------------->
+// | nonce  | src_eid | amount_ld | compose_from    | compose_msg  |
+// | 8B     | 4B      | 8B        | 32B             | N bytes      |
+pub const NONCE_OFFSET: usize = 0;
+pub const SRC_EID_OFFSET: usize = 8;
+pub const AMOUNT_LD_OFFSET: usize = 12;
+pub const COMPOSE_FROM_OFFSET: usize = 20;
+pub const COMPOSE_MSG_OFFSET: usize = 52;
+
+// sha256("global:send")[..8], the Anchor discriminator of the OFT `send` instruction
+pub const SEND_DISCRIMINATOR: [u8; 8] = [102, 251, 20, 187, 65, 75, 12, 69];
+
+#[error_code(offset = 7200)]
+pub enum OftError {
+    /// Compose message shorter than the fixed OFT header
+    InvalidComposeMsg,
+}
+
+pub struct OftComposeMsg {
+    pub nonce: u64,
+    pub src_eid: u32,
+    pub amount_ld: u64,
+    pub compose_from: [u8; 32],
+    pub compose_msg: Vec<u8>,
+}
+
+pub fn decode_compose_msg(message: &[u8]) -> std::result::Result<OftComposeMsg, OftError> {
+    if message.len() < COMPOSE_MSG_OFFSET {
+        return Err(OftError::InvalidComposeMsg);
+    }
+    Ok(OftComposeMsg {
+        nonce: u64::from_be_bytes(message[NONCE_OFFSET..SRC_EID_OFFSET].try_into().unwrap()),
+        src_eid: u32::from_be_bytes(message[SRC_EID_OFFSET..AMOUNT_LD_OFFSET].try_into().unwrap()),
+        amount_ld: u64::from_be_bytes(message[AMOUNT_LD_OFFSET..COMPOSE_FROM_OFFSET].try_into().unwrap()),
+        compose_from: message[COMPOSE_FROM_OFFSET..COMPOSE_MSG_OFFSET].try_into().unwrap(),
+        compose_msg: message[COMPOSE_MSG_OFFSET..].to_vec(),
+    })
+}
+
+// Returns the big-endian src_eid bytes, or an empty slice for a malformed message so PDA seeds simply
+// fail to match
+pub fn compose_src_eid(message: &[u8]) -> &[u8] {
+    message.get(SRC_EID_OFFSET..AMOUNT_LD_OFFSET).unwrap_or(&[])
+}
+
+// Returns the app payload after the OFT header, or an empty slice for a malformed message
+pub fn compose_payload(message: &[u8]) -> &[u8] {
+    message.get(COMPOSE_MSG_OFFSET..).unwrap_or(&[])
+}
+
+// Mirrors the OFT program's `SendParams`
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct OftSendParams {
+    pub dst_eid: u32,
+    pub to: [u8; 32],
+    pub amount_ld: u64,
+    pub min_amount_ld: u64,
+    pub options: Vec<u8>,
+    pub compose_msg: Option<Vec<u8>>,
+    pub native_fee: u64,
+    pub lz_token_fee: u64,
+}
+
+// CPI into OFT `send` with `signer` (the owner of the token source) signing through `signer_seeds`.
+// `accounts` are the OFT send accounts that follow the signer: peer, oft_store, token_source,
+// token_escrow, token_mint, token_program, event_authority, program, then the Endpoint send accounts.
+pub fn send<'info>(
+    oft_program: Pubkey,
+    signer: AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[u8]],
+    params: OftSendParams,
+) -> Result<()> {
+    let mut data = SEND_DISCRIMINATOR.to_vec();
+    params.serialize(&mut data)?;
+
+    let mut metas = vec![AccountMeta::new_readonly(signer.key(), true)];
+    metas.extend(accounts.iter().map(|acc| AccountMeta {
+        pubkey: acc.key(),
+        is_signer: acc.is_signer,
+        is_writable: acc.is_writable,
+    }));
+    let mut infos = vec![signer];
+    infos.extend_from_slice(accounts);
+
+    invoke_signed(&Instruction { program_id: oft_program, accounts: metas, data }, &infos, &[signer_seeds])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose_message(payload: &[u8]) -> Vec<u8> {
+        let mut message = 42u64.to_be_bytes().to_vec();
+        message.extend_from_slice(&30101u32.to_be_bytes());
+        message.extend_from_slice(&1_000_000u64.to_be_bytes());
+        message.extend_from_slice(&[7; 32]);
+        message.extend_from_slice(payload);
+        message
+    }
+
+    #[test]
+    fn compose_messages_decode_field_by_field() {
+        let message = compose_message(&[1, 2, 3]);
+        let compose = decode_compose_msg(&message).unwrap();
+        assert_eq!((compose.nonce, compose.src_eid, compose.amount_ld), (42, 30101, 1_000_000));
+        assert_eq!(compose.compose_from, [7; 32]);
+        assert_eq!(compose.compose_msg, vec![1, 2, 3]);
+        assert_eq!(compose_src_eid(&message), 30101u32.to_be_bytes());
+        assert_eq!(compose_payload(&message), [1, 2, 3]);
+
+        // The payload may be empty
+        assert!(decode_compose_msg(&compose_message(&[])).unwrap().compose_msg.is_empty());
+    }
+
+    #[test]
+    fn truncated_compose_messages_are_rejected() {
+        let message = compose_message(&[]);
+        let truncated = &message[..COMPOSE_MSG_OFFSET - 1];
+        assert!(matches!(decode_compose_msg(truncated), Err(OftError::InvalidComposeMsg)));
+        assert!(compose_payload(truncated).is_empty());
+        assert!(compose_src_eid(&message[..AMOUNT_LD_OFFSET - 1]).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::*;
+
+// Seeds of the PDAs the program derives at runtime: the accounts LzReceiveTypes and LzComposeTypes
+// list, and the message accounts lz_receive and lz_compose check or create themselves. The `seeds`
+// constraints of the Accounts structs don't use these helpers and spell their seeds out instead, so
+// the LzReceiveTypes tests derive the addresses from raw seeds to catch the two drifting apart.
+pub struct PdaSeeds(Vec<Vec<u8>>);
+
+impl PdaSeeds {
+    pub fn address(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&self.as_slices(), program_id)
+    }
+
+    // The seeds followed by the bump, to sign for the PDA
+    pub fn signer<'a>(&'a self, bump: &'a [u8; 1]) -> Vec<&'a [u8]> {
+        let mut seeds = self.as_slices();
+        seeds.push(bump);
+        seeds
+    }
+
+    fn as_slices(&self) -> Vec<&[u8]> {
+        self.0.iter().map(Vec::as_slice).collect()
+    }
+}
+
+pub fn peer(store: &Pubkey, remote_eid: u32) -> PdaSeeds {
+    PdaSeeds(vec![PEER_SEED.to_vec(), store.to_bytes().to_vec(), remote_eid.to_be_bytes().to_vec()])
+}
+
+pub fn nonce(store: &Pubkey, src_eid: u32, sender: &[u8; 32]) -> PdaSeeds {
+    PdaSeeds(vec![
+        NONCE_SEED.to_vec(),
+        store.to_bytes().to_vec(),
+        src_eid.to_be_bytes().to_vec(),
+        sender.to_vec(),
+    ])
+}
+
+pub fn failed_message(store: &Pubkey, guid: &[u8; 32]) -> PdaSeeds {
+    PdaSeeds(vec![FAILED_MESSAGE_SEED.to_vec(), store.to_bytes().to_vec(), guid.to_vec()])
+}
+
+pub fn fee_vault(store: &Pubkey) -> PdaSeeds {
+    PdaSeeds(vec![FEE_VAULT_SEED.to_vec(), store.to_bytes().to_vec()])
+}
+
+pub fn response_accounts(store: &Pubkey) -> PdaSeeds {
+    PdaSeeds(vec![RESPONSE_ACCOUNTS_SEED.to_vec(), store.to_bytes().to_vec()])
+}
+
+pub fn identity_link(store: &Pubkey, src_eid: u32, user: &[u8; 32]) -> PdaSeeds {
+    PdaSeeds(vec![
+        IDENTITY_LINK_SEED.to_vec(),
+        store.to_bytes().to_vec(),
+        src_eid.to_be_bytes().to_vec(),
+        user.to_vec(),
+    ])
+}
+
+// Every pool, local or cross-chain, by its global id
+pub fn bet_pool(store: &Pubkey, pool_id: u64) -> PdaSeeds {
+    PdaSeeds(vec![BET_POOL_SEED.to_vec(), store.to_bytes().to_vec(), pool_id.to_le_bytes().to_vec()])
+}
+
+pub fn creator_pool_index(store: &Pubkey, origin_eid: u32, creator: &[u8; 32], creator_pool_id: u64) -> PdaSeeds {
+    PdaSeeds(vec![
+        CREATOR_POOL_INDEX_SEED.to_vec(),
+        store.to_bytes().to_vec(),
+        origin_eid.to_be_bytes().to_vec(),
+        creator.to_vec(),
+        creator_pool_id.to_le_bytes().to_vec(),
+    ])
+}
+
+// Bets placed from another chain, keyed by the end user there rather than by a Solana signer
+pub fn remote_bet(src_eid: u32, user: &[u8; 32], bet_pool: &Pubkey) -> PdaSeeds {
+    PdaSeeds(vec![
+        BET_SEED.to_vec(),
+        src_eid.to_be_bytes().to_vec(),
+        user.to_vec(),
+        bet_pool.to_bytes().to_vec(),
+    ])
+}
+
+pub fn pool_vault(bet_pool: &Pubkey) -> PdaSeeds {
+    PdaSeeds(vec![POOL_VAULT_SEED.to_vec(), bet_pool.to_bytes().to_vec()])
+}
@@ -1,13 +1,31 @@
 use anchor_lang::prelude::*;
+use crate::state::IdentityLink;
 
 #[account]
 #[derive(InitSpace)]
 pub struct Bet {
     pub authority: Pubkey,
-    pub user: [u8; 32],
+    pub user: [u8; 32], // Signer of a local bet, remote bettor of a cross-chain one (see msg_codec::remote_user)
+    pub origin_eid: u32, // Chain a cross-chain bet came from, 0 for bets placed on Solana
     pub pool: Pubkey, // Pool being bet on
     pub option: u8, // Option index
     pub amount: u64, // Amount bet
     pub claimed: bool, // Whether winnings have been claimed
     pub bump: u8, // PDA bump
+}
+
+impl Bet {
+    // Whether `key` may claim, cash out or refund this bet: its authority, or for a cross-chain bet the
+    // Solana key its remote user linked
+    pub fn is_managed_by(&self, key: Pubkey, identity_link: Option<&IdentityLink>) -> bool {
+        if self.authority == key {
+            return true;
+        }
+        identity_link.is_some_and(|link| {
+            self.origin_eid != 0
+                && link.src_eid == self.origin_eid
+                && link.user == self.user
+                && link.solana_pubkey == key
+        })
+    }
 } 
\ No newline at end of file
@@ -1,23 +1,37 @@
 use anchor_lang::prelude::*;
 
+pub const MAX_QUESTION_LEN: usize = 256;
+pub const MIN_OPTIONS: usize = 2;
+pub const MAX_OPTIONS: usize = 8;
+pub const MAX_RESOLUTION_SOURCE_LEN: usize = 128;
+pub const MAX_EVIDENCE_URI_LEN: usize = 200;
+
 #[account]
 #[derive(InitSpace)]
 pub struct BetPool {
     pub id: u64, // Unique pool id
-    pub creator: Pubkey, // Pool creator
-    #[max_len(256)]
+    pub creator: Pubkey, // Pool creator, the default key for pools created cross-chain
+    pub origin_eid: u32, // Source endpoint id for pools created cross-chain, 0 for local pools
+    pub remote_creator: [u8; 32], // Remote user who created, and may resolve, a cross-chain pool
+    #[max_len(MAX_QUESTION_LEN)]
     pub question: String, // Prediction question
     pub status: PoolStatus, // Open, Closed, Resolved
     pub winning_option: u8, // Index of winning option
     pub start_time: i64, // When betting opens
     pub lock_time: i64, // When betting closes
     pub end_time: i64, // When market resolves
+    pub resolution_deadline: i64, // After this the pool can be voided if still unresolved
     pub unique_bettors: u64, // Number of unique bettors
     pub pool_amount: u64, // Total amount in pool
-    #[max_len(8)]
+    #[max_len(MAX_OPTIONS)]
     pub option_amounts: Vec<u64>, // Total amount bet per option
     pub is_result_set: bool, // Has result been set
     pub result: u8, // Winning option (redundant with winning_option for clarity)
+    #[max_len(MAX_RESOLUTION_SOURCE_LEN)]
+    pub resolution_source: String, // Where the outcome will be taken from, declared at creation
+    #[max_len(MAX_EVIDENCE_URI_LEN)]
+    pub evidence_uri: String, // Link to the evidence submitted by the resolver
+    pub evidence_hash: [u8; 32], // Hash of the evidence content, for disputes and audits
     pub bump: u8, // PDA bump
 }
 
@@ -26,4 +40,21 @@ pub enum PoolStatus {
     Open,
     Closed,
     Resolved,
+    Voided,
+}
+
+impl BetPool {
+    // How long the creator has after end_time to resolve before anyone can void the pool
+    pub const RESOLUTION_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+    // Moves an Open pool to Closed once lock_time has passed, so callers only need to look at status
+    pub fn sync_status(&mut self, now: i64) {
+        if self.status == PoolStatus::Open && now >= self.lock_time {
+            self.status = PoolStatus::Closed;
+        }
+    }
+
+    pub fn is_accepting_bets(&self, now: i64) -> bool {
+        self.status == PoolStatus::Open && now >= self.start_time
+    }
 } 
\ No newline at end of file
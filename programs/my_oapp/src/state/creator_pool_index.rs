@@ -0,0 +1,13 @@
+use crate::*;
+
+// Maps a creator's own id for a pool to the pool's global id, so creators can find their pools
+// without knowing the order pools were created in. Also keeps a creator from reusing an id.
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorPoolIndex {
+    pub origin_eid: u32, // 0 for pools created on Solana
+    pub creator: [u8; 32], // Solana creator, or the remote creator of a cross-chain pool
+    pub creator_pool_id: u64,
+    pub pool_id: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,20 @@
+use crate::*;
+
+// A message from a non-blocking peer whose actions failed. It was cleared on the Endpoint, and the
+// payload is kept here until `retry_failed_message` executes it successfully.
+#[account]
+pub struct FailedMessage {
+    pub src_eid: u32,
+    pub sender: [u8; 32],
+    pub nonce: u64,
+    pub guid: [u8; 32],
+    pub error_code: u32, // Error the last attempt failed with
+    pub message: Vec<u8>,
+    pub bump: u8,
+}
+
+impl FailedMessage {
+    pub fn space(message_len: usize) -> usize {
+        8 + 4 + 32 + 8 + 32 + 4 + (4 + message_len) + 1
+    }
+}
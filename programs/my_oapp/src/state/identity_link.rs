@@ -0,0 +1,13 @@
+use crate::*;
+
+// Solana key allowed to manage the bets of a remote (src_eid, user), set by a LinkWallet message.
+// It can claim and refund them once stakes are held in tokens; until then they are paid out on their
+// source chain.
+#[account]
+#[derive(InitSpace)]
+pub struct IdentityLink {
+    pub src_eid: u32,
+    pub user: [u8; 32], // EVM address of the bettor, left-padded
+    pub solana_pubkey: Pubkey, // Default when unlinked
+    pub bump: u8,
+}
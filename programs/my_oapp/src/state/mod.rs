@@ -2,7 +2,17 @@ pub mod store;
 mod peer_config;
 pub mod bet_pool;
 pub mod bet;
+mod nonce;
+mod failed_message;
+mod identity_link;
+mod creator_pool_index;
+mod response_accounts;
 
 pub use store::*; 
 pub use peer_config::*;
-pub use bet_pool::*;
\ No newline at end of file
+pub use bet_pool::*;
+pub use nonce::*;
+pub use failed_message::*;
+pub use identity_link::*;
+pub use creator_pool_index::*;
+pub use response_accounts::*;
\ No newline at end of file
@@ -0,0 +1,84 @@
+use crate::*;
+use crate::errors::MyOAppError;
+
+// Highest nonce received from one (src_eid, sender) path
+#[account]
+pub struct Nonce {
+    pub bump: u8,
+    pub max_received_nonce: u64,
+}
+
+impl Nonce {
+    pub const SIZE: usize = 8 + std::mem::size_of::<Self>();
+
+    // The nonce an ordered path must deliver next, or None once the path has used up every nonce
+    pub fn next(&self) -> Option<u64> {
+        self.max_received_nonce.checked_add(1)
+    }
+
+    // Records a received nonce. Ordered paths only take the next nonce in line.
+    pub fn accept(&mut self, nonce: u64, ordered: bool) -> Result<()> {
+        if ordered {
+            require!(self.next() == Some(nonce), MyOAppError::InvalidNonce);
+        }
+        // Track the max nonce either way, so turning ordering on later rejects anything older
+        self.skip(nonce);
+        Ok(())
+    }
+
+    // Counts a nonce as received without executing it, so the one after it can execute
+    pub fn skip(&mut self, nonce: u64) {
+        self.max_received_nonce = self.max_received_nonce.max(nonce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonce(max_received_nonce: u64) -> Nonce {
+        Nonce { bump: 0, max_received_nonce }
+    }
+
+    #[test]
+    fn ordered_paths_take_only_the_next_nonce() {
+        let mut path = nonce(0);
+        path.accept(1, true).unwrap();
+        assert_eq!(path.accept(1, true).unwrap_err(), MyOAppError::InvalidNonce.into());
+        assert_eq!(path.accept(3, true).unwrap_err(), MyOAppError::InvalidNonce.into());
+        path.accept(2, true).unwrap();
+        assert_eq!(path.max_received_nonce, 2);
+        assert_eq!(path.next(), Some(3));
+    }
+
+    #[test]
+    fn unordered_paths_take_any_nonce_and_track_the_max() {
+        let mut path = nonce(0);
+        path.accept(5, false).unwrap();
+        path.accept(2, false).unwrap();
+        assert_eq!(path.max_received_nonce, 5);
+        // Turning ordering on rejects anything up to the max received
+        assert_eq!(path.accept(3, true).unwrap_err(), MyOAppError::InvalidNonce.into());
+        path.accept(6, true).unwrap();
+    }
+
+    #[test]
+    fn exhausted_paths_reject_instead_of_overflowing() {
+        let mut path = nonce(u64::MAX);
+        assert_eq!(path.next(), None);
+        assert_eq!(path.accept(0, true).unwrap_err(), MyOAppError::InvalidNonce.into());
+        path.accept(7, false).unwrap();
+        assert_eq!(path.max_received_nonce, u64::MAX);
+    }
+
+    #[test]
+    fn skipped_nonces_unblock_the_next_one() {
+        let mut path = nonce(3);
+        assert_eq!(path.accept(5, true).unwrap_err(), MyOAppError::InvalidNonce.into());
+        path.skip(4);
+        path.accept(5, true).unwrap();
+        // Skipping an older nonce doesn't move the path back
+        path.skip(2);
+        assert_eq!(path.next(), Some(6));
+    }
+}
@@ -2,17 +2,84 @@ use crate::*;
 
 pub const ENFORCED_OPTIONS_SEND_MAX_LEN: usize = 512;
 pub const ENFORCED_OPTIONS_SEND_AND_CALL_MAX_LEN: usize = 1024;
+pub const ALL_MSG_TYPES: u16 = u16::MAX;
 
 #[account]
 #[derive(InitSpace)]
 pub struct PeerConfig {
     pub peer_address: [u8; 32],
     pub enforced_options: EnforcedOptions,
+    pub response_fee: u64, // Native fee budget for messages sent back to this peer from lz_receive
+    pub ordered_nonce: bool, // Execute messages from this peer strictly in nonce order
+    pub allowed_msg_types: u16, // Bit n set = inbound messages of msg_type n are accepted
+    pub message_limit: RateLimit, // Inbound actions (batch items count individually)
+    pub stake_limit: RateLimit, // Total amount of inbound PlaceBets
+    pub paused: bool, // Emergency stop for messaging with this peer
+    pub non_blocking: bool, // Store messages whose actions fail instead of reverting the receive
+    pub notify_failures: bool, // Send MessageFailed back when a message is stored
+    pub ack_mode: AckMode, // Whether and how received messages are acknowledged to this peer
+    pub initialized: bool, // Set by the first set_peer_config, which gives the peer its defaults
     pub bump: u8,
 }
 
 impl PeerConfig {
-    pub const SIZE: usize = 8 + std::mem::size_of::<Self>();
+    pub fn allows(&self, msg_type: u8) -> bool {
+        msg_type < 16 && self.allowed_msg_types & (1 << msg_type) != 0
+    }
+
+    // Charges the rate limits for `actions` inbound actions staking `stake` in total. Shared by
+    // lz_receive and lz_compose. Neither bucket is charged unless both have room.
+    pub fn consume_inbound(&mut self, actions: u64, stake: u64, now: i64) -> Result<()> {
+        let (mut message_limit, mut stake_limit) = (self.message_limit.clone(), self.stake_limit.clone());
+        require!(message_limit.consume(actions, now), BetlifyError::MessageRateLimited);
+        require!(stake_limit.consume(stake, now), BetlifyError::StakeRateLimited);
+        self.message_limit = message_limit;
+        self.stake_limit = stake_limit;
+        Ok(())
+    }
+}
+
+// How lz_receive acknowledges messages from a peer. Acks cost `response_fee` each.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AckMode {
+    Off,
+    // The Store fee vault reimburses the payer for the ack
+    FeeVault,
+}
+
+// Token bucket that holds up to `capacity` and refills completely over `window` seconds
+#[derive(Clone, Default, AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct RateLimit {
+    pub capacity: u64, // 0 disables the limit
+    pub window: i64,
+    pub available: u64,
+    pub last_updated: i64,
+}
+
+impl RateLimit {
+    pub fn new(capacity: u64, window: i64, now: i64) -> Self {
+        RateLimit { capacity, window, available: capacity, last_updated: now }
+    }
+
+    // Takes `amount` from the bucket, or returns false and leaves it untouched if there isn't enough
+    pub fn consume(&mut self, amount: u64, now: i64) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        let elapsed = now.saturating_sub(self.last_updated).max(0) as u128;
+        let refill = if self.window <= 0 {
+            self.capacity as u128
+        } else {
+            elapsed * self.capacity as u128 / self.window as u128
+        };
+        let available = (self.available as u128 + refill).min(self.capacity as u128) as u64;
+        if amount > available {
+            return false;
+        }
+        self.available = available - amount;
+        self.last_updated = now;
+        true
+    }
 }
 
 #[derive(Clone, Default, AnchorSerialize, AnchorDeserialize, InitSpace)]
@@ -41,3 +108,54 @@ impl EnforcedOptions {
         oapp::options::combine_options(enforced_options, extra_options)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_refills_over_the_window() {
+        let mut limit = RateLimit::new(100, 60, 0);
+        assert!(limit.consume(100, 0));
+        assert!(!limit.consume(1, 0));
+        // Half the window refills half the capacity
+        assert!(limit.consume(50, 30));
+        assert!(!limit.consume(1, 30));
+        // Refills never exceed the capacity
+        assert!(!limit.consume(101, 1_000));
+        assert!(limit.consume(100, 1_000));
+    }
+
+    #[test]
+    fn rate_limit_rejects_without_spending() {
+        let mut limit = RateLimit::new(10, 60, 0);
+        assert!(!limit.consume(11, 0));
+        assert!(limit.consume(10, 0));
+    }
+
+    fn allowing(allowed_msg_types: u16) -> PeerConfig {
+        PeerConfig { allowed_msg_types, ..crate::test_utils::peer([1; 32]) }
+    }
+
+    #[test]
+    fn peers_only_accept_the_message_types_they_allow() {
+        let peer = allowing(1 << msg_codec::PLACE_BET_TYPE | 1 << msg_codec::CLAIM_WINNINGS_TYPE);
+        assert!(peer.allows(msg_codec::PLACE_BET_TYPE));
+        assert!(peer.allows(msg_codec::CLAIM_WINNINGS_TYPE));
+        assert!(!peer.allows(msg_codec::CREATE_POOL_TYPE));
+        assert!(!peer.allows(msg_codec::RESOLVE_MARKET_TYPE));
+        assert!(!allowing(ALL_MSG_TYPES).allows(16));
+    }
+
+    #[test]
+    fn peers_with_an_empty_mask_accept_no_message_type() {
+        let peer = allowing(0);
+        assert!((0..16).all(|msg_type| !peer.allows(msg_type)));
+    }
+
+    #[test]
+    fn zero_capacity_disables_the_limit() {
+        let mut limit = RateLimit::default();
+        assert!(limit.consume(u64::MAX, 0));
+    }
+}
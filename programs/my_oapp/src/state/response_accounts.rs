@@ -0,0 +1,54 @@
+use crate::*;
+use oapp::endpoint_cpi::LzAccount;
+
+// Accounts lz_receive sends responses (payouts, acks and failure notices) back to a source chain
+// with. They depend on the send library configured for the path, so the admin registers them per
+// endpoint id and LzReceiveTypes appends them after the Endpoint::clear accounts.
+#[account]
+pub struct ResponseAccounts {
+    pub bump: u8,
+    pub routes: Vec<ResponseRoute>,
+}
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct ResponseRoute {
+    pub eid: u32,
+    pub send: Vec<ResponseAccount>, // Endpoint::send accounts, with the default key standing in for the payer
+    pub oft_send: Vec<ResponseAccount>, // OFT send accounts, for payouts and stake refunds once stakes are held in tokens
+}
+
+#[derive(Clone, Copy, AnchorSerialize, AnchorDeserialize)]
+pub struct ResponseAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ResponseAccounts {
+    pub fn space(routes: &[ResponseRoute]) -> usize {
+        let routes_len: usize = routes
+            .iter()
+            .map(|route| 4 + 4 + 4 + (route.send.len() + route.oft_send.len()) * ResponseAccount::SIZE)
+            .sum();
+        8 + 1 + 4 + routes_len
+    }
+
+    pub fn route(&self, eid: u32) -> Option<&ResponseRoute> {
+        self.routes.iter().find(|route| route.eid == eid)
+    }
+
+    // Number of Endpoint::send accounts lz_receive takes for responses to `eid`
+    pub fn send_len(&self, eid: u32) -> usize {
+        self.route(eid).map_or(0, |route| route.send.len())
+    }
+}
+
+impl ResponseAccount {
+    pub const SIZE: usize = 32 + 1 + 1;
+}
+
+impl From<&ResponseAccount> for LzAccount {
+    fn from(account: &ResponseAccount) -> Self {
+        LzAccount { pubkey: account.pubkey, is_signer: account.is_signer, is_writable: account.is_writable }
+    }
+}
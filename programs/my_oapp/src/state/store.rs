@@ -7,21 +7,60 @@ pub struct Store {
     pub bump: u8,
     pub endpoint_program: Pubkey,
     pub pools_count: u64,
+    pub keeper_reward: u64, // Lamports paid from the Store to whoever cranks close_betting
+    pub min_pool_duration: i64, // Shortest allowed start_time..end_time span, in seconds
+    pub max_pool_duration: i64, // Longest allowed start_time..end_time span, in seconds
+    pub stake_mint: Pubkey, // Token used for cross-chain stakes and payouts, default when unset
+    pub oft_program: Pubkey, // OFT program that bridges stake_mint
+    pub oft_store: Pubkey, // OFT store PDA, the `from` of compose messages carrying stakes
+    pub stake_ld2sd_rate: u64, // The OFT's local to shared decimals rate; it drops amounts below it as dust
+    pub pauser: Pubkey, // May pause alongside the admin, but not unpause
+    pub paused: bool, // Emergency stop for pools, bets, claims, refunds and messaging
+    pub claims_while_paused: bool, // Let claims through while paused
+    pub refunds_while_paused: bool, // Let refunds through while paused
 }
 
 impl Store {
+    pub const DEFAULT_MIN_POOL_DURATION: i64 = 5 * 60;
+    pub const DEFAULT_MAX_POOL_DURATION: i64 = 365 * 24 * 60 * 60;
     pub const MAX_STRING_LENGTH: usize = 256;
     pub const SIZE: usize = 8 + std::mem::size_of::<Self>() + Self::MAX_STRING_LENGTH;
+
+    pub fn claims_open(&self) -> bool {
+        !self.paused || self.claims_while_paused
+    }
+
+    pub fn refunds_open(&self) -> bool {
+        !self.paused || self.refunds_while_paused
+    }
+
+    // Rounds an amount paid out through the stake OFT down to what it can send, so the send's
+    // min_amount_ld holds. The dust stays in the pool vault.
+    pub fn without_stake_dust(&self, amount: u64) -> u64 {
+        amount - amount % self.stake_ld2sd_rate.max(1)
+    }
 }
 
 // The LzReceiveTypesAccounts PDA is used by the Executor as a prerequisite to calling `lz_receive`.
+// The Executor passes the accounts it lists to `lz_receive_types`.
 #[account]
 pub struct LzReceiveTypesAccounts {
     pub store: Pubkey, // This is required and should be consistent.
+    pub response_accounts: Pubkey, // Lets lz_receive_types return the accounts responses are sent with
 }
 
 impl LzReceiveTypesAccounts {
     pub const SIZE: usize = 8 + std::mem::size_of::<Self>();
 }
 
+// The LzComposeTypesAccounts PDA is used by the Executor as a prerequisite to calling `lz_compose`.
+// The Executor passes the accounts it lists to `lz_compose_types`.
+#[account]
+pub struct LzComposeTypesAccounts {
+    pub store: Pubkey, // This is required and should be consistent.
+    pub response_accounts: Pubkey, // Lets lz_compose_types return the OFT send accounts stakes are refunded with
+}
 
+impl LzComposeTypesAccounts {
+    pub const SIZE: usize = 8 + std::mem::size_of::<Self>();
+}
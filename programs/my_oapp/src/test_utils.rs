@@ -0,0 +1,242 @@
+// Lets the instruction handlers run in unit tests. Accounts are leaked so they live as long as the
+// 'info lifetimes handlers expect, and the clock is set per test thread.
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::{
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_stubs::{self, SyscallStubs},
+};
+
+use crate::*;
+use crate::state::bet::Bet;
+
+// Source chain, peer address and time shared by the tests of the cross-chain instructions
+pub const SRC_EID: u32 = 30101;
+pub const SENDER: [u8; 32] = [7; 32];
+pub const NOW: i64 = 1_700_000_000;
+pub const HOUR: i64 = 60 * 60;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(0) };
+    static INVOKED: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Syscalls;
+
+impl SyscallStubs for Syscalls {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW.with(Cell::get), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    // Records every CPI, and carries out System Program transfers so lamport movements can be checked
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+        // SystemInstruction::Transfer is variant 2 followed by the lamports, little-endian
+        let data = &instruction.data;
+        if instruction.program_id != system_program::ID || data.len() != 12 || data[..4] != 2u32.to_le_bytes() {
+            return Ok(());
+        }
+        let lamports = u64::from_le_bytes(data[4..].try_into().unwrap());
+        let info = |meta: &AccountMeta| account_infos.iter().find(|info| *info.key == meta.pubkey).unwrap();
+        let (from, to) = (info(&instruction.accounts[0]), info(&instruction.accounts[1]));
+        let balance = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+        **from.try_borrow_mut_lamports()? = balance;
+        **to.try_borrow_mut_lamports()? += lamports;
+        Ok(())
+    }
+}
+
+// Sets the time Clock::get returns on this thread
+pub fn set_now(now: i64) {
+    static SYSCALLS: Once = Once::new();
+    SYSCALLS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(Syscalls));
+    });
+    NOW.with(|cell| cell.set(now));
+}
+
+// Takes the CPIs made on this thread since the last call
+pub fn take_invoked() -> Vec<Instruction> {
+    INVOKED.with(|invoked| invoked.take())
+}
+
+pub fn account_info(key: Pubkey, owner: Pubkey, lamports: u64, data: Vec<u8>) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        false,
+        true,
+        Box::leak(Box::new(lamports)),
+        Box::leak(data.into_boxed_slice()),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+pub fn signer_info(key: Pubkey) -> AccountInfo<'static> {
+    let mut info = account_info(key, system_program::ID, 1_000_000_000, Vec::new());
+    info.is_signer = true;
+    info
+}
+
+pub fn signer(key: Pubkey) -> Signer<'static> {
+    Signer::try_from(&*Box::leak(Box::new(signer_info(key)))).unwrap()
+}
+
+pub fn system_program() -> Program<'static, System> {
+    let mut info = account_info(system_program::ID, Pubkey::default(), 1, Vec::new());
+    info.executable = true;
+    Program::try_from(&*Box::leak(Box::new(info))).unwrap()
+}
+
+// A program owned account holding `value`, in `space` bytes
+pub fn program_account<T: AccountSerialize>(key: Pubkey, value: &T, space: usize) -> AccountInfo<'static> {
+    let mut data = vec![0; space];
+    value.try_serialize(&mut &mut data[..]).unwrap();
+    account_info(key, crate::ID, Rent::default().minimum_balance(space), data)
+}
+
+pub fn load<T: AccountSerialize + AccountDeserialize + Owner + Clone>(
+    info: AccountInfo<'static>,
+) -> Account<'static, T> {
+    Account::try_from(Box::leak(Box::new(info))).unwrap()
+}
+
+pub fn read<T: AccountDeserialize>(info: &AccountInfo) -> T {
+    T::try_deserialize(&mut &info.data.borrow()[..]).unwrap()
+}
+
+pub fn store_key() -> Pubkey {
+    Pubkey::find_program_address(&[STORE_SEED], &crate::ID).0
+}
+
+pub fn store() -> Store {
+    Store {
+        admin: Pubkey::new_from_array([1; 32]),
+        bump: Pubkey::find_program_address(&[STORE_SEED], &crate::ID).1,
+        endpoint_program: oapp::endpoint::ID,
+        pools_count: 0,
+        keeper_reward: 0,
+        min_pool_duration: Store::DEFAULT_MIN_POOL_DURATION,
+        max_pool_duration: Store::DEFAULT_MAX_POOL_DURATION,
+        stake_mint: Pubkey::default(),
+        oft_program: Pubkey::default(),
+        oft_store: Pubkey::default(),
+        stake_ld2sd_rate: 1,
+        pauser: Pubkey::new_from_array([2; 32]),
+        paused: false,
+        claims_while_paused: false,
+        refunds_while_paused: false,
+    }
+}
+
+// A pool with 100 staked on each of its two options, resolved to option 1 unless voided
+pub fn bet_pool(status: PoolStatus) -> BetPool {
+    BetPool {
+        id: 0,
+        creator: store().admin,
+        origin_eid: 0,
+        remote_creator: [0; 32],
+        question: "Who will win?".to_string(),
+        is_result_set: status == PoolStatus::Resolved,
+        status,
+        winning_option: 1,
+        start_time: 0,
+        lock_time: 1,
+        end_time: 2,
+        resolution_deadline: 3,
+        unique_bettors: 2,
+        pool_amount: 200,
+        option_amounts: vec![100, 100],
+        result: 1,
+        resolution_source: String::new(),
+        evidence_uri: String::new(),
+        evidence_hash: [0; 32],
+        bump: 0,
+    }
+}
+
+// A bet of 100 on option 1 of `pool`, placed on Solana by `authority`
+pub fn bet(authority: Pubkey, pool: Pubkey) -> Bet {
+    Bet { authority, user: [0; 32], origin_eid: 0, pool, option: 1, amount: 100, claimed: false, bump: 0 }
+}
+
+// A peer that accepts every message type and keeps the messages that fail
+pub fn peer(peer_address: [u8; 32]) -> PeerConfig {
+    PeerConfig {
+        peer_address,
+        enforced_options: EnforcedOptions::default(),
+        response_fee: 0,
+        ordered_nonce: false,
+        allowed_msg_types: ALL_MSG_TYPES,
+        message_limit: RateLimit::default(),
+        stake_limit: RateLimit::default(),
+        paused: false,
+        non_blocking: true,
+        notify_failures: false,
+        ack_mode: AckMode::Off,
+        initialized: true,
+        bump: 0,
+    }
+}
+
+pub fn store_account(store: &Store) -> Account<'static, Store> {
+    load(program_account(store_key(), store, Store::SIZE))
+}
+
+pub fn evm_user(id: u8) -> [u8; 20] {
+    [id; 20]
+}
+
+// A two option pool that opens at NOW, locks an hour later and ends an hour after that
+pub fn create_pool(creator: [u8; 20], creator_pool_id: u64) -> msg_codec::BetlifyMessage {
+    msg_codec::BetlifyMessage::CreatePool {
+        creator,
+        question: "Who will win?".to_string(),
+        options: vec!["A".to_string(), "B".to_string()],
+        start_time: NOW,
+        lock_time: NOW + HOUR,
+        end_time: NOW + 2 * HOUR,
+        creator_pool_id,
+        resolution_source: "espn.com".to_string(),
+    }
+}
+
+pub fn place_bet(authority: [u8; 20], pool_id: u64, option: u8, amount: u64) -> msg_codec::BetlifyMessage {
+    msg_codec::BetlifyMessage::PlaceBet { authority, pool_id, option, amount }
+}
+
+pub fn resolve(resolver: [u8; 20], pool_id: u64, winning_option: u8) -> msg_codec::BetlifyMessage {
+    msg_codec::BetlifyMessage::ResolveMarket {
+        resolver,
+        pool_id,
+        winning_option,
+        evidence_uri: "ipfs://evidence".to_string(),
+        evidence_hash: [7; 32],
+    }
+}
+
+pub fn claim(authority: [u8; 20], pool_id: u64) -> msg_codec::BetlifyMessage {
+    msg_codec::BetlifyMessage::ClaimWinnings { authority, pool_id }
+}
+
+// `message` as a client sends it at NOW
+pub fn encoded(message: &msg_codec::BetlifyMessage) -> Vec<u8> {
+    msg_codec::encode_betlify_message(7, NOW as u64, message)
+}
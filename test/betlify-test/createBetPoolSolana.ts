@@ -56,11 +56,23 @@ export async function createBetPoolAccount(
     const provider = new AnchorProvider(connection, wallet as any, {});
 
     const program = new Program(idl as Idl, PROGRAM_ID, provider);
-    // Derive the BetPool PDA (correct: strip 0x and use hex encoding)
+    // Pools are numbered globally by the Store's pool count; poolId is the creator's own id for it
     console.log(`payer.publicKey: ${payer.publicKey.toBase58()}`);
-    const [betPoolPda, bump] = PublicKey.findProgramAddressSync(
+    const [storePda] = PublicKey.findProgramAddressSync([Buffer.from('Store')], PROGRAM_ID);
+    const store = await program.account.store.fetch(storePda);
+    const [betPoolPda] = PublicKey.findProgramAddressSync(
         [
             Buffer.from('betpool'),
+            storePda.toBuffer(),
+            new BN(store.poolsCount).toArrayLike(Buffer, 'le', 8)
+        ],
+        PROGRAM_ID
+    );
+    const [creatorPoolIndexPda] = PublicKey.findProgramAddressSync(
+        [
+            Buffer.from('CreatorPoolIndex'),
+            storePda.toBuffer(),
+            new BN(0).toArrayLike(Buffer, 'be', 4),
             payer.publicKey.toBuffer(),
             new BN(poolId).toArrayLike(Buffer, 'le', 8)
         ],
@@ -68,7 +80,7 @@ export async function createBetPoolAccount(
     );
 
     console.log(`Creating BetPool account: ${betPoolPda.toBase58()}`);
-    console.log(`Pool ID: ${poolId}`);
+    console.log(`Pool ID: ${store.poolsCount} (creator pool ID: ${poolId})`);
     console.log(`Creator: ${creator}`);
     console.log(`Question: ${question}`);
     console.log(`Start: ${start_time}, Lock: ${lock_time}, End: ${end_time}`);
@@ -80,14 +92,18 @@ export async function createBetPoolAccount(
         startTime:  new BN(start_time),
         lockTime: new BN(lock_time),
         endTime: new BN(end_time),
+        creatorPoolId: new BN(poolId),
+        resolutionSource: '',
     };
       
 
     try {
-        const tx = await program.methods.createPool(params, new BN(poolId))
+        const tx = await program.methods.createPool(params)
         .accounts({
             betPool: betPoolPda,
+            creatorPoolIndex: creatorPoolIndexPda,
             creator: payer.publicKey,
+            store: storePda,
             systemProgram: SystemProgram.programId
         }).rpc();
 
@@ -25,7 +25,18 @@ async function main() {
     const providerUrl = process.env.ETH_RPC_URL || 'https://sepolia.optimism.io';
     const privateKey = process.env.PRIVATE_KEY || '';
     console.log('Step 2: Sending create pool message from EVM...');
-    await sendCreatePoolEvm({ providerUrl, privateKey, question, options, start_time, lock_time, end_time, poolId });
+    const resolutionSource = 'coingecko.com';
+    await sendCreatePoolEvm({
+        providerUrl,
+        privateKey,
+        question,
+        options,
+        start_time,
+        lock_time,
+        end_time,
+        poolId,
+        resolutionSource,
+    });
 
     console.log('Done!');
 }
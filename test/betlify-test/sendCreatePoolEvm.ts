@@ -10,6 +10,15 @@ const BETLIFY_EVM_ADAPTER_ABI = require('../../artifacts/contracts/BetlifyOApp.s
 // Replace with your LayerZero destination EID for Solana
 const DST_EID = 40168;
 
+// BetlifyMsgCodec.MsgType.CreatePool
+const CREATE_POOL = 0;
+
+// BetlifyMsgCodec.encodeString: u32 length followed by the UTF-8 bytes
+function encodeString(s: string): string {
+    const bytes = ethers.utils.toUtf8Bytes(s);
+    return ethers.utils.solidityPack(['uint32', 'bytes'], [bytes.length, bytes]);
+}
+
 export async function sendCreatePoolEvm({
     providerUrl,
     privateKey,
@@ -19,6 +28,7 @@ export async function sendCreatePoolEvm({
     lock_time,
     end_time,
     poolId,
+    resolutionSource,
     dstEid = DST_EID,
     optionsData = '0x',
     solanaRpcUrl = 'https://api.devnet.solana.com',
@@ -31,6 +41,7 @@ export async function sendCreatePoolEvm({
     lock_time: number;
     end_time: number;
     poolId: number;
+    resolutionSource: string;
     dstEid?: number;
     optionsData?: string;
     solanaRpcUrl?: string;
@@ -50,14 +61,21 @@ export async function sendCreatePoolEvm({
     const wallet = new ethers.Wallet(privateKey, provider);
     const contract = new ethers.Contract(BETLIFY_EVM_ADAPTER_ADDRESS, BETLIFY_EVM_ADAPTER_ABI, wallet);
 
-    // Estimate fee using the contract's quoteSend (if available)
-    // You may need to adjust this if your contract exposes a different fee estimation method
-    // For now, we'll use 0 as a placeholder and let the contract revert if not enough is sent
-    // If your contract exposes quoteSend for createPool, use it here
+    // The adapter creates the pool as the sender of the transaction, so only the rest of the CreatePool
+    // body is passed. poolId is the creator's own id for the pool; Solana assigns the global one.
+    const args = ethers.utils.hexConcat([
+        encodeString(question),
+        ethers.utils.solidityPack(['uint32'], [options.length]),
+        ...options.map(encodeString),
+        ethers.utils.solidityPack(['int64', 'int64', 'int64', 'uint64'], [start_time, lock_time, end_time, poolId]),
+        encodeString(resolutionSource),
+    ]);
+
+    // Estimate fee using the contract's quoteAction (if available)
     let fee = ethers.BigNumber.from('0');
-    if (contract.quoteSend) {
+    if (contract.quoteAction) {
         try {
-            fee = await contract.quoteSend(dstEid, question, options, start_time, lock_time, end_time, poolId, optionsData);
+            fee = (await contract.quoteAction(dstEid, CREATE_POOL, args, optionsData, false)).nativeFee;
             if (fee.isZero()) {
                 console.warn('Estimated fee is zero, using fallback value.');
                 fee = ethers.utils.parseEther('0.001');
@@ -70,19 +88,8 @@ export async function sendCreatePoolEvm({
         fee = ethers.utils.parseEther('0.001');
     }
 
-    // Call the high-level createPool function
     console.log('Creating pool...');
-    const tx = await contract.createPool(
-        dstEid,
-        question,
-        options,
-        start_time,
-        lock_time,
-        end_time,
-        poolId,
-        optionsData,
-        { value: fee }
-    );
+    const tx = await contract.sendAction(dstEid, CREATE_POOL, args, optionsData, { value: fee });
     console.log('Sent create pool message. Tx:', tx.hash);
     await tx.wait();
     console.log('Transaction confirmed.');
@@ -100,6 +107,17 @@ if (require.main === module) {
         const lock_time = now + 3600;
         const end_time = now + 7200;
         const poolId = 1;
-        await sendCreatePoolEvm({ providerUrl, privateKey, question, options, start_time, lock_time, end_time, poolId });
+        const resolutionSource = 'coingecko.com';
+        await sendCreatePoolEvm({
+            providerUrl,
+            privateKey,
+            question,
+            options,
+            start_time,
+            lock_time,
+            end_time,
+            poolId,
+            resolutionSource,
+        });
     })();
 } 
\ No newline at end of file
@@ -10,10 +10,12 @@ const BETLIFY_EVM_ADAPTER_ABI = require('../../artifacts/contracts/BetlifyOApp.s
 // Replace with your LayerZero destination EID for Solana
 const DST_EID = 40168;
 
+// BetlifyMsgCodec.MsgType.PlaceBet
+const PLACE_BET = 1;
+
 export async function sendPlaceBetEvm({
     providerUrl,
     privateKey,
-    authority,
     pool,
     option,
     amount,
@@ -23,7 +25,6 @@ export async function sendPlaceBetEvm({
 }: {
     providerUrl: string;
     privateKey: string;
-    authority: string;
     pool: number;
     option: number;
     amount: number;
@@ -47,11 +48,14 @@ export async function sendPlaceBetEvm({
     const wallet = new ethers.Wallet(privateKey, provider);
     const contract = new ethers.Contract(BETLIFY_EVM_ADAPTER_ADDRESS, BETLIFY_EVM_ADAPTER_ABI, wallet);
 
-    // Estimate fee using the contract's quoteSend (if available)
+    // The adapter bets as the sender of the transaction, so only the rest of the PlaceBet body is passed
+    const args = ethers.utils.solidityPack(['uint64', 'uint8', 'uint64'], [pool, option, amount]);
+
+    // Estimate fee using the contract's quoteAction (if available)
     let fee = ethers.BigNumber.from('0');
-    if (contract.quoteSend) {
+    if (contract.quoteAction) {
         try {
-            fee = await contract.quoteSend(dstEid, authority, pool, option, amount, optionsData);
+            fee = (await contract.quoteAction(dstEid, PLACE_BET, args, optionsData, false)).nativeFee;
             if (fee.isZero()) {
                 console.warn('Estimated fee is zero, using fallback value.');
                 fee = ethers.utils.parseEther('0.001');
@@ -64,17 +68,8 @@ export async function sendPlaceBetEvm({
         fee = ethers.utils.parseEther('0.001');
     }
 
-    // Call the high-level placeBet function
     console.log('Placing bet...');
-    const tx = await contract.placeBet(
-        dstEid,
-        authority,
-        pool,
-        option,
-        amount,
-        optionsData,
-        { value: fee }
-    );
+    const tx = await contract.sendAction(dstEid, PLACE_BET, args, optionsData, { value: fee });
     console.log('Sent place bet message. Tx:', tx.hash);
     await tx.wait();
     console.log('Transaction confirmed.');
@@ -85,10 +80,9 @@ if (require.main === module) {
     (async () => {
         const providerUrl = process.env.ETH_RPC_URL || '';
         const privateKey = process.env.PRIVATE_KEY || '';
-        const authority = '0x1234567890abcdef1234567890abcdef12345678';
         const pool = 1;
         const option = 0;
         const amount = 1000000; // Example amount
-        await sendPlaceBetEvm({ providerUrl, privateKey, authority, pool, option, amount });
+        await sendPlaceBetEvm({ providerUrl, privateKey, pool, option, amount });
     })();
 } 
\ No newline at end of file
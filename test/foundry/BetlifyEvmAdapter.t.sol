@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.22;
+
+import "forge-std/Test.sol";
+import { ERC20 } from "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+import { Origin } from "@layerzerolabs/oapp-evm/contracts/oapp/OApp.sol";
+import { MessagingFee, MessagingReceipt } from "@layerzerolabs/oapp-evm/contracts/oapp/OAppSender.sol";
+import { TestHelperOz5 } from "@layerzerolabs/test-devtools-evm-foundry/contracts/TestHelperOz5.sol";
+import { BetlifyEvmAdapter } from "../../contracts/BetlifyOApp.sol";
+import { BetlifyMsgCodec } from "../../contracts/libs/BetlifyMsgCodec.sol";
+import { IStakeOFT, SendParam, OFTReceipt } from "../../contracts/interfaces/IStakeOFT.sol";
+
+// Keeps the message the adapter hands to the endpoint instead of sending it
+contract BetlifyEvmAdapterHarness is BetlifyEvmAdapter {
+    bytes public lastMessage;
+
+    constructor(address _endpoint, address _delegate) BetlifyEvmAdapter(_endpoint, _delegate) {}
+
+    function _lzSend(
+        uint32,
+        bytes memory _message,
+        bytes memory,
+        MessagingFee memory,
+        address
+    ) internal override returns (MessagingReceipt memory receipt) {
+        lastMessage = _message;
+        return receipt;
+    }
+}
+
+contract StakeToken is ERC20 {
+    constructor() ERC20("Stake", "STK") {}
+
+    function mint(address to, uint256 amount) external {
+        _mint(to, amount);
+    }
+}
+
+// Locks what it is sent like an OFT adapter and keeps the send instead of bridging it
+contract StakeOFTMock is IStakeOFT {
+    ERC20 public token;
+    SendParam public lastSend;
+    uint256 public lastValue;
+
+    constructor(ERC20 _token) {
+        token = _token;
+    }
+
+    function quoteSend(SendParam calldata, bool) external pure returns (MessagingFee memory) {
+        return MessagingFee(1 ether, 0);
+    }
+
+    function send(
+        SendParam calldata sendParam,
+        MessagingFee calldata,
+        address
+    ) external payable returns (MessagingReceipt memory receipt, OFTReceipt memory oftReceipt) {
+        token.transferFrom(msg.sender, address(this), sendParam.amountLD);
+        lastSend = sendParam;
+        lastValue = msg.value;
+        receipt.guid = keccak256(sendParam.composeMsg);
+        oftReceipt = OFTReceipt(sendParam.amountLD, sendParam.amountLD);
+    }
+}
+
+contract BetlifyEvmAdapterTest is TestHelperOz5 {
+    uint32 constant EVM_EID = 1;
+    uint32 constant SOLANA_EID = 2;
+    uint64 constant TIMESTAMP = 1700000000;
+    address constant USER = address(0x1234);
+    address constant OTHER_USER = address(0x5678);
+
+    bytes32 constant GUID = bytes32(uint256(0xabcd));
+    bytes32 constant SOLANA_PEER = bytes32(uint256(0x50a1));
+
+    BetlifyEvmAdapterHarness adapter;
+    StakeToken token;
+
+    function setUp() public virtual override {
+        super.setUp();
+        setUpEndpoints(1, LibraryType.UltraLightNode);
+        adapter = new BetlifyEvmAdapterHarness(address(endpoints[EVM_EID]), address(this));
+        token = new StakeToken();
+        adapter.setPeer(SOLANA_EID, SOLANA_PEER);
+        vm.warp(TIMESTAMP);
+    }
+
+    // Delivers a message from the Solana peer the way the endpoint does
+    function receiveFromSolana(bytes memory payload) internal {
+        vm.prank(address(endpoints[EVM_EID]));
+        adapter.lzReceive(Origin(SOLANA_EID, SOLANA_PEER, 1), bytes32(0), payload, address(0), "");
+    }
+
+    // USER bets 1000 on option 1 of pool 99 with the stake escrowed
+    function placeEscrowedBet() internal {
+        adapter.setStakeToken(token);
+        token.mint(USER, 1000);
+        vm.startPrank(USER);
+        token.approve(address(adapter), 1000);
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.PlaceBet, abi.encodePacked(uint64(99), uint8(1), uint64(1000)), "");
+        vm.stopPrank();
+    }
+
+    function testActionsAreSentAsTheCaller() public {
+        bytes memory args = abi.encodePacked(uint64(99), uint8(1), uint64(1000));
+        vm.prank(USER);
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.PlaceBet, args, "");
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodePlaceBet(0, TIMESTAMP, USER, 99, 1, 1000));
+    }
+
+    function testWalletsAreOnlyLinkedForTheCaller() public {
+        bytes32 solanaPubkey = bytes32(uint256(0xb0b));
+        vm.prank(USER);
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.LinkWallet, abi.encodePacked(solanaPubkey), "");
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodeLinkWallet(0, TIMESTAMP, USER, solanaPubkey));
+
+        // Another caller sending the same args links their own address, not USER's
+        vm.prank(OTHER_USER);
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.LinkWallet, abi.encodePacked(solanaPubkey), "");
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodeLinkWallet(1, TIMESTAMP, OTHER_USER, solanaPubkey));
+    }
+
+    function testBatchItemsAreSentAsTheCaller() public {
+        BetlifyMsgCodec.MsgType[] memory msgTypes = new BetlifyMsgCodec.MsgType[](2);
+        msgTypes[0] = BetlifyMsgCodec.MsgType.PlaceBet;
+        msgTypes[1] = BetlifyMsgCodec.MsgType.ClaimWinnings;
+        bytes[] memory args = new bytes[](2);
+        args[0] = abi.encodePacked(uint64(99), uint8(1), uint64(1000));
+        args[1] = abi.encodePacked(uint64(98));
+
+        vm.prank(USER);
+        adapter.sendBatch(SOLANA_EID, msgTypes, args, "");
+
+        bytes[] memory items = new bytes[](2);
+        items[0] = BetlifyMsgCodec.encodeBatchItem(msgTypes[0], BetlifyMsgCodec.placeBetBody(USER, 99, 1, 1000));
+        items[1] = BetlifyMsgCodec.encodeBatchItem(msgTypes[1], BetlifyMsgCodec.claimWinningsBody(USER, 98));
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodeBatch(0, TIMESTAMP, items));
+    }
+
+    function testRequestIdsIncrease() public {
+        vm.startPrank(USER);
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.ClaimWinnings, abi.encodePacked(uint64(99)), "");
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.ClaimWinnings, abi.encodePacked(uint64(99)), "");
+        vm.stopPrank();
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodeClaimWinnings(1, TIMESTAMP, USER, 99));
+        assertEq(adapter.nextRequestId(), 2);
+    }
+
+    function testProgramIssuedMessagesCannotBeSent() public {
+        BetlifyMsgCodec.MsgType[3] memory notActions = [
+            BetlifyMsgCodec.MsgType.PayoutIssued,
+            BetlifyMsgCodec.MsgType.MessageFailed,
+            BetlifyMsgCodec.MsgType.ActionAck
+        ];
+        for (uint i = 0; i < notActions.length; i++) {
+            vm.expectRevert(abi.encodeWithSelector(BetlifyEvmAdapter.NotAnAction.selector, notActions[i]));
+            adapter.sendAction(SOLANA_EID, notActions[i], "", "");
+        }
+        vm.expectRevert(
+            abi.encodeWithSelector(BetlifyEvmAdapter.NotAnAction.selector, BetlifyMsgCodec.MsgType.Batch)
+        );
+        adapter.sendAction(SOLANA_EID, BetlifyMsgCodec.MsgType.Batch, "", "");
+    }
+
+    function testBetStakesAreEscrowed() public {
+        placeEscrowedBet();
+        assertEq(token.balanceOf(address(adapter)), 1000);
+        assertEq(token.balanceOf(USER), 0);
+        assertEq(adapter.lastMessage(), BetlifyMsgCodec.encodePlaceBet(0, TIMESTAMP, USER, 99, 1, 1000));
+    }
+
+    function testPayoutsAreReleasedToTheirRecipient() public {
+        placeEscrowedBet();
+        bytes32 recipient = bytes32(uint256(uint160(USER)));
+        vm.expectEmit();
+        emit BetlifyEvmAdapter.PayoutIssued(0, 99, USER, 1000);
+        receiveFromSolana(BetlifyMsgCodec.encodePayoutIssued(0, TIMESTAMP, 99, recipient, 1000));
+        assertEq(adapter.payouts(USER), 1000);
+
+        vm.prank(OTHER_USER);
+        vm.expectRevert(BetlifyEvmAdapter.NothingToWithdraw.selector);
+        adapter.withdrawPayout();
+
+        vm.prank(USER);
+        adapter.withdrawPayout();
+        assertEq(token.balanceOf(USER), 1000);
+        assertEq(adapter.payouts(USER), 0);
+        vm.prank(USER);
+        vm.expectRevert(BetlifyEvmAdapter.NothingToWithdraw.selector);
+        adapter.withdrawPayout();
+    }
+
+    function testAcksAndFailuresAreRecorded() public {
+        bytes32 bet = bytes32(uint256(0x1234));
+        vm.expectEmit();
+        emit BetlifyEvmAdapter.ActionAcked(GUID, 3, BetlifyMsgCodec.ACK_FAILED, 0, bytes32(0));
+        receiveFromSolana(
+            BetlifyMsgCodec.encodeActionAck(3, TIMESTAMP, GUID, BetlifyMsgCodec.ACK_FAILED, 0, bytes32(0))
+        );
+        vm.expectEmit();
+        emit BetlifyEvmAdapter.ActionFailed(GUID, 3, 6002);
+        receiveFromSolana(BetlifyMsgCodec.encodeMessageFailed(3, TIMESTAMP, GUID, 6002));
+        (bool acked, uint8 status, , , uint32 errorCode) = adapter.results(GUID);
+        assertTrue(acked);
+        assertEq(status, BetlifyMsgCodec.ACK_FAILED);
+        assertEq(errorCode, 6002);
+
+        // A retry that succeeds is acked again, with the pool and bet it left its action on
+        receiveFromSolana(
+            BetlifyMsgCodec.encodeActionAck(4, TIMESTAMP, GUID, BetlifyMsgCodec.ACK_EXECUTED, 99, bet)
+        );
+        uint64 poolId;
+        bytes32 ackedBet;
+        (acked, status, poolId, ackedBet, ) = adapter.results(GUID);
+        assertEq(status, BetlifyMsgCodec.ACK_EXECUTED);
+        assertEq(poolId, 99);
+        assertEq(ackedBet, bet);
+    }
+
+    function testActionsAreNotAcceptedFromSolana() public {
+        bytes memory placeBet = BetlifyMsgCodec.encodePlaceBet(0, TIMESTAMP, USER, 99, 1, 1000);
+        vm.expectRevert(
+            abi.encodeWithSelector(BetlifyEvmAdapter.NotAResponse.selector, BetlifyMsgCodec.MsgType.PlaceBet)
+        );
+        receiveFromSolana(placeBet);
+    }
+
+    function testMalformedResponsesAreRejected() public {
+        bytes memory payout = BetlifyMsgCodec.encodePayoutIssued(0, TIMESTAMP, 99, bytes32(0), 1000);
+        vm.expectRevert(
+            abi.encodeWithSelector(BetlifyMsgCodec.InvalidMessageLength.selector, payout.length + 1)
+        );
+        receiveFromSolana(abi.encodePacked(payout, uint8(0)));
+
+        payout[0] = bytes1(uint8(2));
+        vm.expectRevert(abi.encodeWithSelector(BetlifyMsgCodec.UnsupportedVersion.selector, 2));
+        receiveFromSolana(payout);
+    }
+
+    function testStakedBetsComposeThePlaceBetOntoTheStake() public {
+        StakeOFTMock oft = new StakeOFTMock(token);
+        adapter.setStakeToken(token);
+        adapter.setStakeOft(oft);
+        token.mint(USER, 1000);
+        vm.deal(USER, 1 ether);
+
+        bytes memory placeBet = BetlifyMsgCodec.encodePlaceBet(0, TIMESTAMP, USER, 99, 1, 1000);
+        vm.startPrank(USER);
+        token.approve(address(adapter), 1000);
+        assertEq(adapter.quoteBetWithStake(SOLANA_EID, 99, 1, 1000, hex"0003", false).nativeFee, 1 ether);
+        vm.expectEmit();
+        emit BetlifyEvmAdapter.ActionSent(keccak256(placeBet), 0, USER);
+        adapter.sendBetWithStake{ value: 1 ether }(SOLANA_EID, 99, 1, 1000, hex"0003");
+        vm.stopPrank();
+
+        (uint32 dstEid, bytes32 to, uint256 amountLD, uint256 minAmountLD, , bytes memory composeMsg, ) = oft
+            .lastSend();
+        assertEq(dstEid, SOLANA_EID);
+        assertEq(to, SOLANA_PEER);
+        assertEq(amountLD, 1000);
+        assertEq(minAmountLD, 1000);
+        assertEq(composeMsg, placeBet);
+        assertEq(oft.lastValue(), 1 ether);
+        assertEq(token.balanceOf(address(oft)), 1000);
+        assertEq(token.balanceOf(address(adapter)), 0);
+        assertEq(adapter.nextRequestId(), 1);
+    }
+
+    function testStakedBetsNeedTheStakeOft() public {
+        vm.expectRevert(BetlifyEvmAdapter.StakeOftNotSet.selector);
+        adapter.sendBetWithStake(SOLANA_EID, 99, 1, 1000, "");
+    }
+}
@@ -5,7 +5,17 @@ import "forge-std/Test.sol";
 import { BetlifyMsgCodec } from "../../contracts/libs/BetlifyMsgCodec.sol";
 
 contract BetlifyMsgCodecTest is Test {
+    // Same vectors the Solana program's msg_codec tests decode and re-encode
+    string constant VECTORS_PATH = "test/vectors/betlify-msg-codec.json";
+    uint64 constant REQUEST_ID = 7;
+    uint64 constant TIMESTAMP = 1700000000;
+
+    function vector(string memory key) internal view returns (bytes memory) {
+        return vm.parseJsonBytes(vm.readFile(VECTORS_PATH), key);
+    }
+
     function testEncodeCreatePool() public {
+        address creator = address(0x1234);
         string memory question = "Who will win?";
         string[] memory options = new string[](2);
         options[0] = "A";
@@ -13,17 +23,26 @@ contract BetlifyMsgCodecTest is Test {
         int64 startTime = 1700000000;
         int64 lockTime = 1700003600;
         int64 endTime = 1700007200;
-        uint64 poolId = 42;
+        uint64 creatorPoolId = 42;
+        string memory resolutionSource = "espn.com";
 
         bytes memory encoded = BetlifyMsgCodec.encodeCreatePool(
-            question, options, startTime, lockTime, endTime, poolId
+            REQUEST_ID,
+            TIMESTAMP,
+            creator,
+            question,
+            options,
+            startTime,
+            lockTime,
+            endTime,
+            creatorPoolId,
+            resolutionSource
         );
 
-        // Check the message type prefix
-        assertEq(uint8(encoded[0]), 0); // MsgType.CreatePool
-        // Optionally, decode and check the rest of the fields
-        // (You can use abi.decode on encoded[1:] if you want to check the struct)
-        assertGt(encoded.length, 1);
+        // Check the header prefix
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 0); // MsgType.CreatePool
+        assertEq(encoded, vector(".createPool"));
     }
 
     function testEncodePlaceBet() public {
@@ -31,25 +50,163 @@ contract BetlifyMsgCodecTest is Test {
         uint64 poolId = 99;
         uint8 option = 1;
         uint64 amount = 1000;
-        bytes memory encoded = BetlifyMsgCodec.encodePlaceBet(authority, poolId, option, amount);
-        assertEq(uint8(encoded[0]), 1); // MsgType.PlaceBet
-        assertGt(encoded.length, 1);
+        bytes memory encoded = BetlifyMsgCodec.encodePlaceBet(REQUEST_ID, TIMESTAMP, authority, poolId, option, amount);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 1); // MsgType.PlaceBet
+        assertEq(encoded, vector(".placeBet"));
     }
 
     function testEncodeResolveMarket() public {
+        address resolver = address(0x1234);
         uint64 poolId = 99;
         uint8 winningOption = 1;
-        bytes memory encoded = BetlifyMsgCodec.encodeResolveMarket(poolId, winningOption);
-        assertEq(uint8(encoded[0]), 2); // MsgType.ResolveMarket
-        assertGt(encoded.length, 1);
+        string memory evidenceUri = "ipfs://evidence";
+        bytes32 evidenceHash = bytes32(uint256(0x1234));
+        bytes memory encoded = BetlifyMsgCodec.encodeResolveMarket(
+            REQUEST_ID,
+            TIMESTAMP,
+            resolver,
+            poolId,
+            winningOption,
+            evidenceUri,
+            evidenceHash
+        );
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 2); // MsgType.ResolveMarket
+        assertEq(encoded, vector(".resolveMarket"));
     }
 
     function testEncodeClaimWinnings() public {
+        address authority = address(0x1234);
         uint64 poolId = 99;
-        bytes memory encoded = BetlifyMsgCodec.encodeClaimWinnings(poolId);
-        assertEq(uint8(encoded[0]), 3); // MsgType.ClaimWinnings
-        assertGt(encoded.length, 1);
+        bytes memory encoded = BetlifyMsgCodec.encodeClaimWinnings(REQUEST_ID, TIMESTAMP, authority, poolId);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 3); // MsgType.ClaimWinnings
+        assertEq(encoded, vector(".claimWinnings"));
+    }
+
+    function testEncodePayoutIssued() public {
+        uint64 poolId = 99;
+        bytes32 recipient = bytes32(uint256(0x1234));
+        uint64 amount = 1000;
+        bytes memory encoded = BetlifyMsgCodec.encodePayoutIssued(REQUEST_ID, TIMESTAMP, poolId, recipient, amount);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 4); // MsgType.PayoutIssued
+        assertEq(encoded, vector(".payoutIssued"));
+    }
+
+    function testEncodeLinkWallet() public {
+        address authority = address(0x1234);
+        bytes32 solanaPubkey = bytes32(uint256(0x5678));
+        bytes memory encoded = BetlifyMsgCodec.encodeLinkWallet(REQUEST_ID, TIMESTAMP, authority, solanaPubkey);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 8); // MsgType.LinkWallet
+        assertEq(encoded, vector(".linkWallet"));
+    }
+
+    function testEncodeMessageFailed() public {
+        bytes32 guid = bytes32(uint256(0xabcd));
+        uint32 errorCode = 6002;
+        bytes memory encoded = BetlifyMsgCodec.encodeMessageFailed(REQUEST_ID, TIMESTAMP, guid, errorCode);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 6); // MsgType.MessageFailed
+        assertEq(encoded, vector(".messageFailed"));
+    }
+
+    function testEncodeActionAck() public {
+        bytes32 guid = bytes32(uint256(0xabcd));
+        bytes32 bet = bytes32(uint256(0x1234));
+        bytes memory encoded = BetlifyMsgCodec.encodeActionAck(REQUEST_ID, TIMESTAMP, guid, 0, 99, bet);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 7); // MsgType.ActionAck
+        assertEq(encoded, vector(".actionAck"));
+    }
+
+    function testEncodeBatch() public {
+        string[] memory options = new string[](2);
+        options[0] = "A";
+        options[1] = "B";
+        bytes[] memory items = new bytes[](2);
+        items[0] = BetlifyMsgCodec.encodeBatchItem(
+            BetlifyMsgCodec.MsgType.CreatePool,
+            BetlifyMsgCodec.createPoolBody(
+                address(0x1234), "Who will win?", options, 1700000000, 1700003600, 1700007200, 42, "espn.com"
+            )
+        );
+        items[1] = BetlifyMsgCodec.encodeBatchItem(
+            BetlifyMsgCodec.MsgType.PlaceBet,
+            BetlifyMsgCodec.placeBetBody(address(0x1234), 42, 1, 1000)
+        );
+        bytes memory encoded = BetlifyMsgCodec.encodeBatch(REQUEST_ID, TIMESTAMP, items);
+        assertEq(uint8(encoded[0]), 1); // version
+        assertEq(uint8(encoded[1]), 5); // MsgType.Batch
+        assertEq(encoded, vector(".batch"));
+    }
+
+    function testEncodeBatchRejectsBadLengths() public {
+        vm.expectRevert(abi.encodeWithSelector(BetlifyMsgCodec.InvalidBatchLength.selector, 0));
+        this.encodeBatch(new bytes[](0));
+        vm.expectRevert(abi.encodeWithSelector(BetlifyMsgCodec.InvalidBatchLength.selector, 5));
+        this.encodeBatch(new bytes[](5));
+    }
+
+    // External so expectRevert can catch reverts from the internal library call
+    function encodeBatch(bytes[] memory items) external pure returns (bytes memory) {
+        return BetlifyMsgCodec.encodeBatch(REQUEST_ID, TIMESTAMP, items);
+    }
+
+    function testDecodeHeader() public {
+        (BetlifyMsgCodec.MsgType msgType, uint64 requestId, uint64 timestamp) = this.decodeHeader(vector(".payoutIssued"));
+        assertEq(uint8(msgType), uint8(BetlifyMsgCodec.MsgType.PayoutIssued));
+        assertEq(requestId, REQUEST_ID);
+        assertEq(timestamp, TIMESTAMP);
+    }
+
+    function testDecodePayoutIssued() public {
+        (uint64 poolId, bytes32 recipient, uint64 amount) = this.decodePayoutIssued(vector(".payoutIssued"));
+        assertEq(poolId, 99);
+        assertEq(recipient, bytes32(uint256(0x1234)));
+        assertEq(amount, 1000);
+    }
+
+    function testDecodeMessageFailed() public {
+        (bytes32 guid, uint32 errorCode) = this.decodeMessageFailed(vector(".messageFailed"));
+        assertEq(guid, bytes32(uint256(0xabcd)));
+        assertEq(errorCode, 6002);
     }
 
-    // TODO: Add round-trip decode tests if/when decode functions are implemented
+    function testDecodeActionAck() public {
+        (bytes32 guid, uint8 status, uint64 poolId, bytes32 bet) = this.decodeActionAck(vector(".actionAck"));
+        assertEq(guid, bytes32(uint256(0xabcd)));
+        assertEq(status, BetlifyMsgCodec.ACK_EXECUTED);
+        assertEq(poolId, 99);
+        assertEq(bet, bytes32(uint256(0x1234)));
+    }
+
+    function testDecodeRejectsOtherLengths() public {
+        bytes memory ack = vector(".actionAck");
+        vm.expectRevert(abi.encodeWithSelector(BetlifyMsgCodec.InvalidMessageLength.selector, ack.length + 1));
+        this.decodeActionAck(abi.encodePacked(ack, uint8(0)));
+        vm.expectRevert(abi.encodeWithSelector(BetlifyMsgCodec.InvalidMessageLength.selector, 17));
+        this.decodeHeader(new bytes(17));
+    }
+
+    // External so the library reads calldata, like the adapter's _lzReceive does
+    function decodeHeader(
+        bytes calldata message
+    ) external pure returns (BetlifyMsgCodec.MsgType, uint64, uint64) {
+        return BetlifyMsgCodec.decodeHeader(message);
+    }
+
+    function decodePayoutIssued(bytes calldata message) external pure returns (uint64, bytes32, uint64) {
+        return BetlifyMsgCodec.decodePayoutIssued(message);
+    }
+
+    function decodeMessageFailed(bytes calldata message) external pure returns (bytes32, uint32) {
+        return BetlifyMsgCodec.decodeMessageFailed(message);
+    }
+
+    function decodeActionAck(bytes calldata message) external pure returns (bytes32, uint8, uint64, bytes32) {
+        return BetlifyMsgCodec.decodeActionAck(message);
+    }
 } 
\ No newline at end of file